@@ -2,17 +2,33 @@
 
 mod state;
 
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+
 use linera_sdk::{
-    linera_base_types::WithContractAbi,
+    linera_base_types::{Account, AccountOwner, Amount, ChainId, StreamName, WithContractAbi},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
 use sha2::{Sha256, Digest};
 
-use dcdn::Operation;
-use dcdn::{DCDNResponse, ContentMetadata};
+use dcdn::{
+    DCDNEvent, DCDNParameters, EconomicChange, Message, ModerationAction, NetworkConfig, NodeAdmission,
+    NodeRegistration, Operation, PauseTarget, required_approvals,
+};
+use dcdn::{DCDNResponse, ContentMetadata, NodeLocation, NodeProfile, Pricing, SignedReceipt, UsageRow};
+use dcdn::{
+    CHALLENGE_CHUNK_SIZE, CHALLENGE_INTERVAL_MICROS, CHALLENGE_RESPONSE_WINDOW_MICROS, FAILED_AUDIT_SLASH_PERCENT, INFLATED_USAGE_SLASH_PERCENT,
+    EPOCH_LENGTH_MICROS, FAILURE_REPORT_INTERVAL_MICROS, MAX_RELIABILITY_WINDOW_MICROS, MAX_REPORT_REASON_LEN, MAX_UNREVIEWED_USAGE_BYTES, PURGE_DEADLINE_MICROS, UNBONDING_PERIOD_MICROS, USAGE_HISTORY_MIN_MICROS,
+    USAGE_SPIKE_FACTOR, USAGE_WINDOW_MICROS,
+};
 
-use self::state::{DCDNState, ContentData, NodeInfo};
+use self::state::{
+    AvailabilityEntry, BlockedHash, DCDNState, ContentData, ContentStats, FlaggedReport,
+    IncomingCacheRequest, ModerationRecord, ModerationRecordKind, ModerationStatus, NodeEvent,
+    NodeEventKind, NodeInfo, NodeStatement, OwnerStatement, Proposal, Purge, PurgeStatus, PurgeTarget,
+    RemoteCache, RemoteCacheStatus, Replication, ReplicationStatus, ReviewStatus, ShardRequest,
+    ShardRequestStatus, SlashReason, StorageChallenge,
+};
 
 pub struct DCDNContract {
     state: DCDNState,
@@ -26,10 +42,10 @@ impl WithContractAbi for DCDNContract {
 }
 
 impl Contract for DCDNContract {
-    type Message = ();
-    type Parameters = ();
+    type Message = Message;
+    type Parameters = DCDNParameters;
     type InstantiationArgument = ();
-    type EventValue = ();
+    type EventValue = DCDNEvent;
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
         let state = DCDNState::load(runtime.root_view_storage_context())
@@ -39,29 +55,135 @@ impl Contract for DCDNContract {
     }
 
     async fn instantiate(&mut self, _argument: Self::InstantiationArgument) {
-        // Initialize application parameters
-        self.runtime.application_parameters();
+        let config = self.runtime.application_parameters().config;
+        if let Err(message) = config.validate() {
+            panic!("Invalid network configuration: {message}");
+        }
+        self.initialize_chain();
     }
 
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
+        self.initialize_chain();
+        if let Some(shard_chain) = self.owning_shard(&operation).await {
+            return self.route_to_shard(shard_chain, operation).await;
+        }
         match operation {
             Operation::Upload { content, metadata } => {
                 self.upload_content(content, metadata).await
             }
+            Operation::DeleteContent { content_id } => {
+                self.delete_content(content_id).await
+            }
             Operation::RequestCache { content_id, node_id } => {
                 self.request_cache(content_id, node_id).await
             }
+            Operation::RequestRemoteCache { content_id, node_id, node_chain } => {
+                self.request_remote_cache(content_id, node_id, node_chain).await
+            }
+            Operation::RespondCacheRequest { request_id, accept } => {
+                self.respond_cache_request(request_id, accept).await
+            }
+            Operation::Purge { content_id } => {
+                self.purge(content_id).await
+            }
+            Operation::AcknowledgePurge { content_id, node_id } => {
+                self.acknowledge_purge(content_id, node_id).await
+            }
+            Operation::ExpirePurge { content_id } => {
+                self.expire_purge(content_id).await
+            }
+            Operation::Replicate { content_id, target_chain } => {
+                self.replicate(content_id, target_chain).await
+            }
             Operation::UpdateAvailability { content_id, node_id, available } => {
                 self.update_availability(content_id, node_id, available).await
             }
             Operation::Download { content_id } => {
                 self.download_content(content_id).await
             }
-            Operation::RegisterNode { node_id, location, capacity } => {
-                self.register_node(node_id, location, capacity).await
+            Operation::RegisterNode { node_id, location, capacity, bandwidth, profile, stake } => {
+                self.register_node(node_id, location, capacity, bandwidth, profile, stake).await
+            }
+            Operation::UpdateCapacity { node_id, capacity } => {
+                self.update_capacity(node_id, capacity).await
+            }
+            Operation::DeregisterNode { node_id } => {
+                self.deregister_node(node_id).await
+            }
+            Operation::WithdrawStake { node_id } => {
+                self.withdraw_stake(node_id).await
+            }
+            Operation::UpdateNode { node_id, profile } => {
+                self.update_node(node_id, profile).await
+            }
+            Operation::Heartbeat { node_id } => {
+                self.heartbeat(node_id).await
+            }
+            Operation::ReportNodeFailure { node_id, content_id } => {
+                self.report_node_failure(node_id, content_id).await
+            }
+            Operation::IssueChallenge { content_id, node_id } => {
+                self.issue_challenge(content_id, node_id).await
+            }
+            Operation::RespondChallenge { challenge_id, chunk_hash, proof } => {
+                self.respond_challenge(challenge_id, chunk_hash, proof).await
+            }
+            Operation::ExpireChallenge { challenge_id } => {
+                self.expire_challenge(challenge_id).await
+            }
+            Operation::ReportUsage { node_id, receipts } => {
+                self.report_usage(node_id, receipts).await
+            }
+            Operation::ReportUsageBatch { node_id, window_start, window_end, rows } => {
+                self.report_usage_batch(node_id, window_start, window_end, rows).await
+            }
+            Operation::ReviewUsageReport { report_id, approve } => {
+                self.review_usage_report(report_id, approve).await
+            }
+            Operation::DepositEscrow { amount } => {
+                self.deposit_escrow(amount).await
+            }
+            Operation::WithdrawEscrow { amount } => {
+                self.withdraw_escrow(amount).await
+            }
+            Operation::ReportContent { content_id, reason } => {
+                self.report_content(content_id, reason).await
+            }
+            Operation::Moderate { content_id, action, reason } => {
+                self.moderate(content_id, action, reason).await
+            }
+            Operation::AppealModeration { content_id, statement } => {
+                self.appeal_moderation(content_id, statement).await
+            }
+            Operation::ResolveAppeal { content_id, granted, reason } => {
+                self.resolve_appeal(content_id, granted, reason).await
+            }
+            Operation::BlockHashes { hashes, reason } => {
+                self.block_hashes(hashes, reason).await
             }
-            Operation::ReportUsage { node_id, content_id, bytes_served } => {
-                self.report_usage(node_id, content_id, bytes_served).await
+            Operation::UnblockHashes { hashes } => {
+                self.unblock_hashes(hashes).await
+            }
+            Operation::UpdateConfig { config } => {
+                self.update_config(config).await
+            }
+            Operation::Pause { target } => {
+                self.set_paused(target, true).await
+            }
+            Operation::Unpause { target } => {
+                self.set_paused(target, false).await
+            }
+            Operation::ProposeChange { change } => {
+                self.propose_change(change).await
+            }
+            Operation::ApproveProposal { proposal_id } => {
+                self.approve_proposal(proposal_id).await
+            }
+            Operation::ClaimEarnings => {
+                self.claim_earnings().await
+            }
+            Operation::CloseEpoch { epoch } => {
+                self.close_epoch(epoch).await
             }
             Operation::UpdateMetadata { content_id, metadata } => {
                 self.update_metadata(content_id, metadata).await
@@ -69,7 +191,118 @@ impl Contract for DCDNContract {
         }
     }
 
-    async fn execute_message(&mut self, _message: Self::Message) {}
+    async fn execute_message(&mut self, message: Self::Message) {
+        self.initialize_chain();
+        let origin = self.runtime.message_origin_chain_id().expect("Incoming message must have an origin");
+        if self.runtime.message_is_bouncing() == Some(true) {
+            self.handle_bounced_message(origin, message).await;
+            return;
+        }
+        match message {
+            Message::CacheRequest { content_id, node_id, size } => {
+                self.receive_cache_request(origin, content_id, node_id, size).await
+            }
+            Message::CacheConfirmed { content_id, node_id } => {
+                self.receive_cache_answer(origin, content_id, node_id, None).await
+            }
+            Message::CacheRejected { content_id, node_id, reason } => {
+                self.receive_cache_answer(origin, content_id, node_id, Some(reason)).await
+            }
+            Message::CancelCacheRequest { content_id, node_id } => {
+                self.cancel_cache_request(origin, content_id, node_id).await
+            }
+            Message::AvailabilityUpdate { content_id, node_id, available } => {
+                self.receive_availability_update(origin, content_id, node_id, available).await
+            }
+            Message::PurgeRequest { content_id, node_id } => {
+                // Only the chain that asked for the copy may ask for it to be dropped
+                let origin_key = (content_id, node_id);
+                if self.state.cache_origins.get(&origin_key).await.unwrap() == Some(origin) {
+                    self.state.pending_purges.insert(&origin_key, origin).expect("Failed to record purge request");
+                }
+            }
+            Message::PurgeAcknowledged { content_id, node_id } => {
+                self.settle_purge_target(&content_id, &node_id, origin, PurgeStatus::Acknowledged).await;
+            }
+            Message::PurgeExpired { content_id, node_id } => {
+                // Forget the request and the copy, as if the operator had acknowledged
+                let origin_key = (content_id, node_id);
+                if self.state.cache_origins.get(&origin_key).await.unwrap() == Some(origin) {
+                    self.state.pending_purges.remove(&origin_key).expect("Failed to remove purge request");
+                    self.state.cache_origins.remove(&origin_key).expect("Failed to remove cache origin");
+                    let (content_id, node_id) = origin_key;
+                    self.drop_copy(&content_id, &node_id).await;
+                }
+            }
+            Message::ReplicateContent { content_id, content, metadata, owner } => {
+                self.receive_replica(origin, content_id, content, metadata, owner).await
+            }
+            Message::ContentReplicated { content_id } => {
+                self.receive_replication_answer(origin, content_id, None).await
+            }
+            Message::ReplicationFailed { content_id, reason } => {
+                self.receive_replication_answer(origin, content_id, Some(reason)).await
+            }
+            Message::ShardOperation { request_id, operation } => {
+                let error = match self.execute_operation(operation).await {
+                    DCDNResponse::Error { message } => Some(message),
+                    _ => None,
+                };
+                let message = Message::ShardOperationResult { request_id, error };
+                self.runtime.prepare_message(message).with_authentication().send_to(origin);
+            }
+            Message::ShardOperationResult { request_id, error } => {
+                if let Some(mut request) = self.state.shard_requests.get(&request_id).await.unwrap() {
+                    if request.shard_chain == origin && request.status == ShardRequestStatus::Pending {
+                        request.status = if error.is_some() { ShardRequestStatus::Failed } else { ShardRequestStatus::Succeeded };
+                        request.error = error;
+                        self.state.shard_requests.insert(&request_id, request).expect("Failed to record shard request");
+                    }
+                }
+            }
+            Message::DropReplica { content_id } => {
+                // Only the chain the replica came from may withdraw it
+                if self.state.replica_sources.get(&content_id).await.unwrap() == Some(origin) {
+                    self.remove_content(content_id).await;
+                }
+            }
+            Message::FetchContent { content_id } => {
+                // Count the access here, where the content's statistics are kept
+                if let DCDNResponse::DownloadSuccess { content } = self.download_content(content_id.clone()).await {
+                    if let Some(content_data) = self.state.content_map.get(&content_id).await.unwrap() {
+                        self.send_replica(content_id, content, content_data, origin).await;
+                    }
+                }
+            }
+            Message::NodeRegistered { registration } => {
+                if self.is_registry() {
+                    self.receive_node_registration(origin, registration).await;
+                }
+            }
+            Message::NodeHeartbeat { node_id } => {
+                if let Some(mut node_info) = self.registered_node(origin, &node_id).await {
+                    node_info.last_heartbeat = self.runtime.system_time().micros();
+                    self.state.nodes.insert(&node_id, node_info).expect("Failed to update node");
+                    self.record_node_event(&node_id, NodeEventKind::Heartbeat).await;
+                }
+            }
+            Message::NodeCapacityChanged { node_id, capacity } => {
+                if let Some(node_info) = self.registered_node(origin, &node_id).await {
+                    self.set_capacity(node_info, capacity).await;
+                }
+            }
+            Message::NodeDeregistered { node_id } => {
+                if let Some(node_info) = self.registered_node(origin, &node_id).await {
+                    self.retire_node(node_info).await;
+                }
+            }
+            Message::NodeRejected { node_id } => {
+                if origin == self.registry_chain() {
+                    self.receive_node_rejection(node_id).await;
+                }
+            }
+        }
+    }
 
     async fn store(mut self) {
         self.state.save().await.expect("Failed to save state");
@@ -77,51 +310,327 @@ impl Contract for DCDNContract {
 }
 
 impl DCDNContract {
+    /// Copies the network settings from the application parameters the first time the
+    /// application runs on this chain. From then on each chain keeps its own copy, which
+    /// `UpdateConfig` and proposals change on that chain only.
+    fn initialize_chain(&mut self) {
+        if self.state.config.get().is_some() {
+            return;
+        }
+        let config = self.runtime.application_parameters().config;
+        let pricing = Pricing {
+            price_per_byte: config.price_per_byte,
+            ..self.state.pricing.get().clone()
+        };
+        self.state.pricing.set(pricing);
+        self.state.config.set(Some(config));
+    }
+
+    fn config(&self) -> NetworkConfig {
+        self.state.config.get().clone().expect("Network configuration is set when the chain is initialized")
+    }
+
     async fn upload_content(&mut self, content: Vec<u8>, mut metadata: ContentMetadata) -> DCDNResponse {
+        if self.state.pauses.get().uploads {
+            return DCDNResponse::Error {
+                message: "Uploads are paused".to_string()
+            };
+        }
+        let config = self.config();
+        if content.len() as u64 > config.max_upload_size {
+            return DCDNResponse::Error {
+                message: format!("Content exceeds the maximum upload size of {} bytes", config.max_upload_size)
+            };
+        }
+
         // Generate content ID as SHA256 hash of the content
         let mut hasher = Sha256::new();
         hasher.update(&content);
         let content_hash = format!("{:x}", hasher.finalize());
         let content_id = content_hash.clone();
         
-        // Update metadata with the content hash
+        // Update metadata with the content hash and actual size
         metadata.content_hash = Some(content_hash);
+        metadata.size = content.len() as u64;
 
-        // Check if content already exists
-        if self.state.content_map.get(&content_id).await.unwrap().is_some() {
-            return DCDNResponse::Error { 
-                message: "Content with this hash already exists".to_string() 
+        if self.state.blocklist.contains_key(&content_id).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "Content is blocklisted".to_string()
             };
         }
 
+        // Check if content already exists; expired content makes way for the new upload
+        if self.state.content_map.contains_key(&content_id).await.unwrap() {
+            if self.expired_content(&content_id).await.is_none() {
+                return DCDNResponse::Error { 
+                    message: "Content with this hash already exists".to_string() 
+                };
+            }
+            self.remove_content(content_id.clone()).await;
+        }
+
         let current_time = self.runtime.system_time();
         let current_time_ticks = current_time.micros();
+        if metadata.expires_at.is_none() {
+            metadata.expires_at = config.default_ttl_micros.map(|ttl| current_time_ticks.saturating_add(ttl));
+        }
         
+        let size = metadata.size;
+        let owner = self.runtime.authenticated_signer();
         let content_data = ContentData {
             id: content_id.clone(),
-            content,
             metadata,
             created_at: current_time_ticks,
+            owner,
+        };
+        let content_stats = ContentStats {
             last_accessed: current_time_ticks,
-            access_count: 0,
+            ..ContentStats::default()
         };
 
         self.state.content_map.insert(&content_id, content_data).expect("Failed to insert content");
+        self.state.content_bytes.insert(&content_id, content).expect("Failed to insert content bytes");
+        self.state.content_stats.insert(&content_id, content_stats).expect("Failed to insert content stats");
+        self.emit(DCDNEvent::ContentUploaded { content_id: content_id.clone(), size, owner });
         
         DCDNResponse::UploadSuccess { content_id }
     }
 
+    async fn delete_content(&mut self, content_id: String) -> DCDNResponse {
+        if let Err(response) = self.owned_content(&content_id).await {
+            return response;
+        }
+        self.remove_content(content_id).await;
+        DCDNResponse::ContentDeleted
+    }
+
+    /// Removes content with every copy of it, including replicas sent to other chains
+    async fn remove_content(&mut self, content_id: String) {
+        let node_ids = match self.state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders.indices().await.unwrap(),
+            None => Vec::new(),
+        };
+        for node_id in node_ids {
+            self.drop_copy(&content_id, &node_id).await;
+            let origin_key = (content_id.clone(), node_id);
+            self.state.cache_origins.remove(&origin_key).expect("Failed to remove cache origin");
+            self.state.pending_purges.remove(&origin_key).expect("Failed to remove purge request");
+        }
+        self.state.content_availability.remove_entry(&content_id).expect("Failed to clear availability");
+        self.state.remote_caches.remove_entry(&content_id).expect("Failed to clear remote caches");
+        self.drop_replicas(&content_id).await;
+        self.state.replica_sources.remove(&content_id).expect("Failed to remove replica source");
+        self.state.content_map.remove(&content_id).expect("Failed to remove content");
+        self.state.content_bytes.remove(&content_id).expect("Failed to remove content bytes");
+        self.state.content_stats.remove(&content_id).expect("Failed to remove content stats");
+        self.state.purges.remove(&content_id).expect("Failed to remove purge");
+        self.emit(DCDNEvent::ContentDeleted { content_id });
+    }
+
+    /// Drops every local node's copy of some content and every replica sent to other chains,
+    /// and asks the chains of remote nodes holding or asked for a copy to drop or cancel it
+    async fn withdraw_all_copies(&mut self, content_id: &str) {
+        let node_ids = match self.state.content_availability.try_load_entry(content_id).await.unwrap() {
+            Some(holders) => holders.indices().await.unwrap(),
+            None => Vec::new(),
+        };
+        for node_id in node_ids {
+            self.drop_copy(content_id, &node_id).await;
+        }
+        let remote_caches = match self.state.remote_caches.try_load_entry(content_id).await.unwrap() {
+            Some(requests) => requests.index_values().await.unwrap(),
+            None => Vec::new(),
+        };
+        for (_, RemoteCache { node_id, node_chain, status, .. }) in remote_caches {
+            let content_id = content_id.to_string();
+            let message = match status {
+                RemoteCacheStatus::Pending => Message::CancelCacheRequest { content_id, node_id },
+                RemoteCacheStatus::Confirmed => Message::PurgeRequest { content_id, node_id },
+                RemoteCacheStatus::Rejected => continue,
+            };
+            self.runtime.prepare_message(message).with_authentication().send_to(node_chain);
+        }
+        self.state.remote_caches.remove_entry(content_id).expect("Failed to clear remote caches");
+        self.drop_replicas(content_id).await;
+    }
+
+    /// Tells every chain holding or about to hold a replica of some content to drop it, and
+    /// forgets the replications
+    async fn drop_replicas(&mut self, content_id: &str) {
+        let replications = match self.state.replications.try_load_entry(content_id).await.unwrap() {
+            Some(replications) => replications.index_values().await.unwrap(),
+            None => Vec::new(),
+        };
+        for (target_chain, replication) in replications {
+            if replication.status != ReplicationStatus::Failed {
+                let message = Message::DropReplica { content_id: content_id.to_string() };
+                self.runtime.prepare_message(message).with_authentication().send_to(target_chain);
+            }
+        }
+        self.state.replications.remove_entry(content_id).expect("Failed to clear replications");
+    }
+
+    /// Loads content the authenticated signer uploaded, or the error to return if they did not
+    async fn owned_content(&mut self, content_id: &str) -> Result<ContentData, DCDNResponse> {
+        let Some(content_data) = self.state.content_map.get(content_id).await.unwrap() else {
+            return Err(DCDNResponse::Error {
+                message: "Content not found".to_string()
+            });
+        };
+        if content_data.owner.is_none() || self.runtime.authenticated_signer() != content_data.owner {
+            return Err(DCDNResponse::Error {
+                message: "Only the content owner can do this".to_string()
+            });
+        }
+        Ok(content_data)
+    }
+
+    async fn purge(&mut self, content_id: String) -> DCDNResponse {
+        if let Err(response) = self.owned_content(&content_id).await {
+            return response;
+        }
+        if let Some(purge) = self.state.purges.get(&content_id).await.unwrap() {
+            if purge.targets.iter().any(|target| target.status == PurgeStatus::Pending) {
+                return DCDNResponse::Error {
+                    message: "A purge of this content is already in progress".to_string()
+                };
+            }
+        }
+
+        // Local copies are dropped here; copies held by nodes on other chains are dropped there
+        let this_chain = self.runtime.chain_id();
+        let mut targets = Vec::new();
+        if let Some(holders) = self.state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            for node_id in holders.indices().await.unwrap() {
+                targets.push(PurgeTarget { node_id, node_chain: this_chain, status: PurgeStatus::Pending, settled_at: None });
+            }
+        }
+        if let Some(requests) = self.state.remote_caches.try_load_entry(&content_id).await.unwrap() {
+            for remote_cache in requests.index_values().await.unwrap().into_iter().map(|(_, remote_cache)| remote_cache) {
+                if remote_cache.copy.is_none() {
+                    continue;
+                }
+                let RemoteCache { node_id, node_chain, .. } = remote_cache;
+                let message = Message::PurgeRequest { content_id: content_id.clone(), node_id: node_id.clone() };
+                self.runtime.prepare_message(message).with_authentication().send_to(node_chain);
+                targets.push(PurgeTarget { node_id, node_chain, status: PurgeStatus::Pending, settled_at: None });
+            }
+        }
+
+        self.drop_replicas(&content_id).await;
+
+        let requested_at = self.runtime.system_time().micros();
+        let deadline = requested_at + PURGE_DEADLINE_MICROS;
+        let purge = Purge { content_id: content_id.clone(), requested_at, deadline, targets };
+        self.state.purges.insert(&content_id, purge).expect("Failed to record purge");
+        self.emit(DCDNEvent::PurgeRequested { content_id, deadline });
+
+        DCDNResponse::PurgeRequested { deadline }
+    }
+
+    async fn acknowledge_purge(&mut self, content_id: String, node_id: String) -> DCDNResponse {
+        if let Err(response) = self.operated_node(&node_id).await {
+            return response;
+        }
+
+        // A purge requested by the chain that asked this chain's node for the copy
+        let origin_key = (content_id.clone(), node_id.clone());
+        if let Some(origin_chain) = self.state.pending_purges.get(&origin_key).await.unwrap() {
+            self.state.pending_purges.remove(&origin_key).expect("Failed to remove purge request");
+            self.state.cache_origins.remove(&origin_key).expect("Failed to remove cache origin");
+            self.drop_copy(&content_id, &node_id).await;
+            let message = Message::PurgeAcknowledged { content_id, node_id };
+            self.runtime.prepare_message(message).with_authentication().send_to(origin_chain);
+            return DCDNResponse::PurgeAcknowledged;
+        }
+
+        let this_chain = self.runtime.chain_id();
+        if self.settle_purge_target(&content_id, &node_id, this_chain, PurgeStatus::Acknowledged).await {
+            DCDNResponse::PurgeAcknowledged
+        } else {
+            DCDNResponse::Error {
+                message: "No purge is pending for this node".to_string()
+            }
+        }
+    }
+
+    async fn expire_purge(&mut self, content_id: String) -> DCDNResponse {
+        let Some(purge) = self.state.purges.get(&content_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Purge does not exist".to_string()
+            };
+        };
+        if self.runtime.system_time().micros() < purge.deadline {
+            return DCDNResponse::Error {
+                message: "Purge deadline has not passed".to_string()
+            };
+        }
+
+        let mut node_ids = Vec::new();
+        for target in purge.targets {
+            if target.status == PurgeStatus::Pending
+                && self.settle_purge_target(&content_id, &target.node_id, target.node_chain, PurgeStatus::Expired).await
+            {
+                node_ids.push(target.node_id);
+            }
+        }
+
+        DCDNResponse::PurgeExpired { node_ids }
+    }
+
+    /// Marks a node's part in a purge as done and drops its copy, returning whether the
+    /// node was still pending on `node_chain`
+    async fn settle_purge_target(&mut self, content_id: &str, node_id: &str, node_chain: ChainId, status: PurgeStatus) -> bool {
+        let Some(mut purge) = self.state.purges.get(content_id).await.unwrap() else {
+            return false;
+        };
+        let Some(target) = purge.targets.iter_mut().find(|target| {
+            target.node_id == node_id && target.node_chain == node_chain && target.status == PurgeStatus::Pending
+        }) else {
+            return false;
+        };
+        target.status = status;
+        target.settled_at = Some(self.runtime.system_time().micros());
+        self.state.purges.insert(content_id, purge).expect("Failed to update purge");
+
+        if node_chain == self.runtime.chain_id() {
+            self.drop_copy(content_id, node_id).await;
+        } else {
+            // Forget the remote copy so later updates from its chain are ignored
+            let requests = self.state.remote_caches.load_entry_mut(content_id).await.expect("Failed to load remote caches");
+            requests.remove(&(node_chain, node_id.to_string())).expect("Failed to remove cache request");
+            if status == PurgeStatus::Expired {
+                let message = Message::PurgeExpired { content_id: content_id.to_string(), node_id: node_id.to_string() };
+                self.runtime.prepare_message(message).with_authentication().send_to(node_chain);
+            }
+        }
+        true
+    }
+
+    fn emit(&mut self, event: DCDNEvent) {
+        self.runtime.emit(StreamName::from(event.stream_name()), &event);
+    }
+
     async fn download_content(&mut self, content_id: String) -> DCDNResponse {
-        match self.state.content_map.get(&content_id).await.unwrap() {
-            Some(mut content_data) => {
+        if let Some(response) = self.blocked_content(&content_id).await {
+            return response;
+        }
+        if let Some(response) = self.expired_content(&content_id).await {
+            return response;
+        }
+        match self.state.content_bytes.get(&content_id).await.unwrap() {
+            Some(content) => {
                 // Update last accessed time and access count
                 let current_time_ticks = self.runtime.system_time().micros();
-                content_data.last_accessed = current_time_ticks;
-                content_data.access_count += 1;
-                
-                self.state.content_map.insert(&content_id, content_data.clone()).expect("Failed to update content");
+                let stats = self.state.content_stats.get_mut_or_default(&content_id).await.expect("Failed to load content stats");
+                stats.last_accessed = current_time_ticks;
+                stats.access_count += 1;
+                if let Some(client) = self.runtime.authenticated_signer() {
+                    let downloads = self.state.downloads.get_mut_or_default(&(client, content_id)).await.expect("Failed to load downloads");
+                    *downloads += 1;
+                }
                 
-                DCDNResponse::DownloadSuccess { content: content_data.content }
+                DCDNResponse::DownloadSuccess { content }
             },
             None => DCDNResponse::Error { 
                 message: "Content not found".to_string() 
@@ -130,136 +639,3839 @@ impl DCDNContract {
     }
 
     async fn request_cache(&mut self, content_id: String, node_id: String) -> DCDNResponse {
+        if let Some(response) = self.blocked_content(&content_id).await {
+            return response;
+        }
+        if let Some(response) = self.expired_content(&content_id).await {
+            return response;
+        }
         // Check if content exists
-        if self.state.content_map.get(&content_id).await.unwrap().is_none() {
+        if !self.state.content_map.contains_key(&content_id).await.unwrap() {
             return DCDNResponse::Error { 
                 message: "Content does not exist".to_string() 
             };
         }
 
         // Check if node exists
-        if self.state.nodes.get(&node_id).await.unwrap().is_none() {
+        let Some(node_info) = self.state.nodes.get(&node_id).await.unwrap() else {
             return DCDNResponse::Error { 
                 message: "Node does not exist".to_string() 
             };
-        }
+        };
 
-        // Update content availability map
-        let mut availability = self.state.content_availability.get(&content_id).await.unwrap().unwrap_or_default();
-        if !availability.contains(&node_id) {
-            availability.push(node_id);
-        }
-        self.state.content_availability.insert(&content_id, availability).expect("Failed to update availability");
+        let operator_claimed = self.runtime.authenticated_signer() == Some(node_info.operator);
+        self.set_availability(&content_id, &node_id, true, operator_claimed).await;
 
         DCDNResponse::CacheRequestAccepted
     }
 
     async fn update_availability(&mut self, content_id: String, node_id: String, available: bool) -> DCDNResponse {
-        match self.state.content_availability.get(&content_id).await.unwrap() {
-            Some(mut availability) => {
-                if available && !availability.contains(&node_id) {
-                    availability.push(node_id);
-                } else if !available {
-                    availability.retain(|id| id != &node_id);
-                }
-                self.state.content_availability.insert(&content_id, availability).expect("Failed to update availability");
-            },
-            None => {
-                if available {
-                    self.state.content_availability.insert(&content_id, vec![node_id]).expect("Failed to update availability");
-                }
+        if let Err(response) = self.operated_node(&node_id).await {
+            return response;
+        }
+        // Nodes may always drop a blocklisted or moderated copy, but not claim one
+        if available {
+            if let Some(response) = self.blocked_content(&content_id).await {
+                return response;
             }
         }
 
+        self.set_availability(&content_id, &node_id, available, true).await;
+
+        // Let the chain that asked for this copy know it came or went
+        let origin_key = (content_id.clone(), node_id.clone());
+        if let Some(origin_chain) = self.state.cache_origins.get(&origin_key).await.unwrap() {
+            let message = Message::AvailabilityUpdate { content_id, node_id, available };
+            self.runtime.prepare_message(message).with_authentication().send_to(origin_chain);
+        }
+
         DCDNResponse::CacheRequestAccepted
     }
 
-    async fn register_node(&mut self, node_id: String, location: String, capacity: u64) -> DCDNResponse {
-        // Check if node already exists
-        if self.state.nodes.get(&node_id).await.unwrap().is_some() {
+    async fn request_remote_cache(&mut self, content_id: String, node_id: String, node_chain: ChainId) -> DCDNResponse {
+        if let Some(response) = self.blocked_content(&content_id).await {
+            return response;
+        }
+        if let Some(response) = self.expired_content(&content_id).await {
+            return response;
+        }
+        let Some(content_data) = self.state.content_map.get(&content_id).await.unwrap() else {
             return DCDNResponse::Error { 
-                message: "Node already registered".to_string() 
+                message: "Content does not exist".to_string() 
             };
+        };
+        let requests = self.state.remote_caches.load_entry_mut(&content_id).await.expect("Failed to load remote caches");
+        let cache_key = (node_chain, node_id.clone());
+        if let Some(existing) = requests.get(&cache_key).await.unwrap() {
+            if existing.status != RemoteCacheStatus::Rejected {
+                return DCDNResponse::Error {
+                    message: "Node has already been asked to cache this content".to_string()
+                };
+            }
         }
 
-        let current_time_ticks = self.runtime.system_time().micros();
-        let node_info = NodeInfo {
-            id: node_id.clone(),
-            location,
-            capacity,
-            used_capacity: 0,
-            available: true,
-            created_at: current_time_ticks,
-            data_served: 0,
+        let remote_cache = RemoteCache {
+            node_id: node_id.clone(),
+            node_chain,
+            status: RemoteCacheStatus::Pending,
+            requested_at: self.runtime.system_time().micros(),
+            reason: None,
+            copy: None,
         };
+        requests.insert(&cache_key, remote_cache).expect("Failed to record cache request");
 
-        self.state.nodes.insert(&node_id, node_info).expect("Failed to insert node");
-        
-        let count = *self.state.node_count.get();
-        self.state.node_count.set(count + 1);
-        
-        let total = *self.state.total_capacity.get();
-        self.state.total_capacity.set(total + capacity);
+        let message = Message::CacheRequest { content_id, node_id, size: content_data.metadata.size };
+        self.runtime
+            .prepare_message(message)
+            .with_tracking()
+            .with_authentication()
+            .send_to(node_chain);
 
-        DCDNResponse::NodeRegistered
+        DCDNResponse::CacheRequestSent
     }
 
-    async fn report_usage(&mut self, node_id: String, content_id: String, bytes_served: u64) -> DCDNResponse {
-        // Check if node exists
-        if self.state.nodes.get(&node_id).await.unwrap().is_none() {
-            return DCDNResponse::Error { 
-                message: "Node does not exist".to_string() 
-            };
+    async fn receive_cache_request(&mut self, origin: ChainId, content_id: String, node_id: String, size: u64) {
+        let rejection = if self.state.blocklist.contains_key(&content_id).await.unwrap() {
+            Some("Content is blocklisted".to_string())
+        } else {
+            match self.state.nodes.get(&node_id).await.unwrap() {
+                None => Some("Node is not registered on this chain".to_string()),
+                Some(node_info) if !node_info.available || node_info.unbonding_until.is_some() => {
+                    Some("Node is not available".to_string())
+                }
+                Some(node_info) if size > node_info.profile.max_object_size => {
+                    Some("Content exceeds the node's maximum object size".to_string())
+                }
+                Some(_) => None,
+            }
+        };
+        if let Some(reason) = rejection {
+            let message = Message::CacheRejected { content_id, node_id, reason };
+            self.runtime.prepare_message(message).with_authentication().send_to(origin);
+            return;
         }
 
-        // Check if content exists
-        if self.state.content_map.get(&content_id).await.unwrap().is_none() {
-            return DCDNResponse::Error { 
-                message: "Content does not exist".to_string() 
-            };
+        let request_id = *self.state.next_cache_request_id.get();
+        self.state.next_cache_request_id.set(request_id + 1);
+        let request = IncomingCacheRequest {
+            id: request_id,
+            content_id,
+            node_id,
+            origin_chain: origin,
+            size,
+            received_at: self.runtime.system_time().micros(),
+        };
+        self.state.cache_requests.insert(&request_id, request).expect("Failed to record cache request");
+    }
+
+    /// Forgets the unanswered requests the origin chain sent for a node to cache some content.
+    /// If the operator already accepted, the copy is dropped through the purge flow instead.
+    async fn cancel_cache_request(&mut self, origin: ChainId, content_id: String, node_id: String) {
+        let mut request_ids = Vec::new();
+        self.state
+            .cache_requests
+            .for_each_index_value(|request_id, request| {
+                if request.origin_chain == origin && request.content_id == content_id && request.node_id == node_id {
+                    request_ids.push(request_id);
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        for request_id in request_ids {
+            self.state.cache_requests.remove(&request_id).expect("Failed to remove cache request");
         }
 
-        // Update node's served data
-        if let Some(mut node_info) = self.state.nodes.get(&node_id).await.unwrap() {
-            node_info.data_served += bytes_served;
-            node_info.used_capacity = std::cmp::min(node_info.used_capacity + bytes_served, node_info.capacity);
-            self.state.nodes.insert(&node_id, node_info).expect("Failed to update node");
+        let origin_key = (content_id, node_id);
+        if self.state.cache_origins.get(&origin_key).await.unwrap() == Some(origin) {
+            self.state.pending_purges.insert(&origin_key, origin).expect("Failed to record purge request");
+        }
+    }
+
+    async fn respond_cache_request(&mut self, request_id: u64, accept: bool) -> DCDNResponse {
+        let Some(request) = self.state.cache_requests.get(&request_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Cache request does not exist".to_string()
+            };
+        };
+        if let Err(response) = self.operated_node(&request.node_id).await {
+            return response;
         }
+        if accept && self.state.blocklist.contains_key(&request.content_id).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "Content is blocklisted".to_string()
+            };
+        }
+        self.state.cache_requests.remove(&request_id).expect("Failed to remove cache request");
 
-        // Update total data served
-        let total = *self.state.total_data_served.get();
-        self.state.total_data_served.set(total + bytes_served);
+        let IncomingCacheRequest { content_id, node_id, origin_chain, .. } = request;
+        let message = if accept {
+            self.set_availability(&content_id, &node_id, true, true).await;
+            self.state
+                .cache_origins
+                .insert(&(content_id.clone(), node_id.clone()), origin_chain)
+                .expect("Failed to record cache origin");
+            Message::CacheConfirmed { content_id, node_id }
+        } else {
+            let reason = "Declined by the node operator".to_string();
+            Message::CacheRejected { content_id, node_id, reason }
+        };
+        self.runtime.prepare_message(message).with_authentication().send_to(origin_chain);
 
-        DCDNResponse::UsageReported
+        DCDNResponse::CacheRequestAnswered
     }
 
-    async fn update_metadata(&mut self, content_id: String, metadata: ContentMetadata) -> DCDNResponse {
-        match self.state.content_map.get(&content_id).await.unwrap() {
-            Some(mut content_data) => {
-                content_data.metadata = metadata;
-                self.state.content_map.insert(&content_id, content_data).expect("Failed to update content");
-                DCDNResponse::MetadataUpdated
-            },
-            None => DCDNResponse::Error {
-                message: "Content not found".to_string()
+    /// Records a node chain's answer to a pending cache request, ignoring answers from
+    /// chains the request was not sent to
+    async fn receive_cache_answer(&mut self, origin: ChainId, content_id: String, node_id: String, rejection: Option<String>) {
+        let current_time_ticks = self.runtime.system_time().micros();
+        let requests = self.state.remote_caches.load_entry_mut(&content_id).await.expect("Failed to load remote caches");
+        let cache_key = (origin, node_id);
+        let Some(mut remote_cache) = requests.get(&cache_key).await.unwrap() else {
+            return;
+        };
+        if remote_cache.status != RemoteCacheStatus::Pending {
+            return;
+        }
+        if rejection.is_none() {
+            remote_cache.status = RemoteCacheStatus::Confirmed;
+            remote_cache.copy = Some(AvailabilityEntry {
+                claimed_at: current_time_ticks,
+                updated_at: current_time_ticks,
+                operator_claimed: true,
+            });
+        } else {
+            remote_cache.status = RemoteCacheStatus::Rejected;
+        }
+        remote_cache.reason = rejection;
+        requests.insert(&cache_key, remote_cache).expect("Failed to update cache request");
+    }
+
+    async fn receive_availability_update(&mut self, origin: ChainId, content_id: String, node_id: String, available: bool) {
+        let current_time_ticks = self.runtime.system_time().micros();
+        let cache_key = (origin, node_id);
+        let remote_cache = match self.state.remote_caches.try_load_entry(&content_id).await.unwrap() {
+            Some(requests) => requests.get(&cache_key).await.unwrap(),
+            None => None,
+        };
+        let Some(mut remote_cache) = remote_cache else {
+            return;
+        };
+        if remote_cache.status != RemoteCacheStatus::Confirmed {
+            return;
+        }
+        if available && self.blocked_content(&content_id).await.is_some() {
+            return;
+        }
+        remote_cache.copy = match (available, remote_cache.copy) {
+            (false, _) => None,
+            (true, Some(mut copy)) => {
+                copy.updated_at = current_time_ticks;
+                Some(copy)
             }
+            (true, None) => Some(AvailabilityEntry {
+                claimed_at: current_time_ticks,
+                updated_at: current_time_ticks,
+                operator_claimed: true,
+            }),
+        };
+        let requests = self.state.remote_caches.load_entry_mut(&content_id).await.expect("Failed to load remote caches");
+        requests.insert(&cache_key, remote_cache).expect("Failed to update cache request");
+    }
+
+    async fn handle_bounced_message(&mut self, origin: ChainId, message: Message) {
+        match message {
+            // A cache request the node's chain refused to execute is as good as declined
+            Message::CacheRequest { content_id, node_id, .. } => {
+                let requests = self.state.remote_caches.load_entry_mut(&content_id).await.expect("Failed to load remote caches");
+                let cache_key = (origin, node_id);
+                if let Some(mut remote_cache) = requests.get(&cache_key).await.unwrap() {
+                    remote_cache.status = RemoteCacheStatus::Rejected;
+                    remote_cache.reason = Some("Request bounced".to_string());
+                    requests.insert(&cache_key, remote_cache).expect("Failed to update cache request");
+                }
+            }
+            // The copy never arrived, so forget it was sent and let the owner try again
+            Message::ReplicateContent { content_id, .. } => {
+                let replications = self.state.replications.load_entry_mut(&content_id).await.expect("Failed to load replications");
+                if let Some(replication) = replications.get(&origin).await.unwrap() {
+                    if replication.status == ReplicationStatus::Pending {
+                        replications.remove(&origin).expect("Failed to roll back replication");
+                    }
+                }
+            }
+            _ => {}
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use futures::FutureExt as _;
-    use linera_sdk::{util::BlockingWait, views::View, Contract, ContractRuntime};
+    async fn replicate(&mut self, content_id: String, target_chain: ChainId) -> DCDNResponse {
+        if let Some(response) = self.blocked_content(&content_id).await {
+            return response;
+        }
+        if let Some(response) = self.expired_content(&content_id).await {
+            return response;
+        }
+        let content_data = match self.owned_content(&content_id).await {
+            Ok(content_data) => content_data,
+            Err(response) => return response,
+        };
+        if target_chain == self.runtime.chain_id() {
+            return DCDNResponse::Error {
+                message: "Content is already on this chain".to_string()
+            };
+        }
+        let replications = self.state.replications.load_entry_mut(&content_id).await.expect("Failed to load replications");
+        if let Some(existing) = replications.get(&target_chain).await.unwrap() {
+            if existing.status != ReplicationStatus::Failed {
+                return DCDNResponse::Error {
+                    message: "Content has already been replicated to this chain".to_string()
+                };
+            }
+        }
 
-    use dcdn::{Operation, ContentMetadata};
+        let content = self.state.content_bytes.get(&content_id).await.unwrap().unwrap_or_default();
+        self.send_replica(content_id, content, content_data, target_chain).await;
 
-    use super::{DCDNContract, DCDNState};
+        DCDNResponse::ReplicationSent
+    }
 
+    /// Records a pending replication and ships the content to `target_chain`
+    async fn send_replica(&mut self, content_id: String, content: Vec<u8>, content_data: ContentData, target_chain: ChainId) {
+        let replication = Replication {
+            target_chain,
+            status: ReplicationStatus::Pending,
+            requested_at: self.runtime.system_time().micros(),
+            reason: None,
+        };
+        let replications = self.state.replications.load_entry_mut(&content_id).await.expect("Failed to load replications");
+        replications.insert(&target_chain, replication).expect("Failed to record replication");
 
+        let message = Message::ReplicateContent {
+            content_id,
+            content,
+            metadata: content_data.metadata,
+            owner: content_data.owner,
+        };
+        self.runtime
+            .prepare_message(message)
+            .with_tracking()
+            .with_authentication()
+            .send_to(target_chain);
+    }
 
-    fn create_and_instantiate_app() -> DCDNContract {
-        let runtime = ContractRuntime::new().with_application_parameters(());
+    /// Shard chain a content operation must run on, if that is not this chain. Downloads of
+    /// content already replicated here are served locally.
+    async fn owning_shard(&mut self, operation: &Operation) -> Option<ChainId> {
+        let content_id = match operation {
+            Operation::Upload { content, .. } => format!("{:x}", Sha256::digest(content)),
+            Operation::Download { content_id } => {
+                if self.state.content_bytes.contains_key(content_id).await.unwrap() {
+                    return None;
+                }
+                content_id.clone()
+            }
+            Operation::UpdateMetadata { content_id, .. } | Operation::DeleteContent { content_id } => content_id.clone(),
+            _ => return None,
+        };
+        let shard_chain = self.runtime.application_parameters().shard_for(&content_id)?;
+        (shard_chain != self.runtime.chain_id()).then_some(shard_chain)
+    }
+
+    /// Forwards an operation to its shard chain. The shard answers a download by replicating
+    /// the content to this chain, and any other operation with its outcome.
+    async fn route_to_shard(&mut self, shard_chain: ChainId, operation: Operation) -> DCDNResponse {
+        let (message, request_id) = match operation {
+            Operation::Download { content_id } => (Message::FetchContent { content_id }, None),
+            operation => {
+                let request_id = *self.state.next_shard_request_id.get();
+                self.state.next_shard_request_id.set(request_id + 1);
+                let request = ShardRequest {
+                    id: request_id,
+                    shard_chain,
+                    sent_at: self.runtime.system_time().micros(),
+                    status: ShardRequestStatus::Pending,
+                    error: None,
+                };
+                self.state.shard_requests.insert(&request_id, request).expect("Failed to record shard request");
+                (Message::ShardOperation { request_id, operation }, Some(request_id))
+            }
+        };
+        self.runtime.prepare_message(message).with_authentication().send_to(shard_chain);
+        DCDNResponse::RoutedToShard { shard_chain, request_id }
+    }
+
+    /// Stores content replicated from another chain if its bytes hash to its ID, and tells
+    /// the sending chain the outcome
+    async fn receive_replica(
+        &mut self,
+        origin: ChainId,
+        content_id: String,
+        content: Vec<u8>,
+        metadata: ContentMetadata,
+        owner: Option<AccountOwner>,
+    ) {
+        let content_hash = format!("{:x}", Sha256::digest(&content));
+        let max_upload_size = self.config().max_upload_size;
+        let message = if content_hash != content_id {
+            let reason = "Content does not match its SHA-256 hash".to_string();
+            Message::ReplicationFailed { content_id, reason }
+        } else if content.len() as u64 > max_upload_size {
+            let reason = format!("Content exceeds the maximum upload size of {max_upload_size} bytes");
+            Message::ReplicationFailed { content_id, reason }
+        } else if self.state.blocklist.contains_key(&content_id).await.unwrap() {
+            let reason = "Content is blocklisted".to_string();
+            Message::ReplicationFailed { content_id, reason }
+        } else {
+            // The same ID means the same bytes, so an existing copy is kept as it is
+            if !self.state.content_map.contains_key(&content_id).await.unwrap() {
+                let current_time_ticks = self.runtime.system_time().micros();
+                let size = content.len() as u64;
+                let content_data = ContentData {
+                    id: content_id.clone(),
+                    metadata: ContentMetadata {
+                        size,
+                        content_hash: Some(content_hash),
+                        ..metadata
+                    },
+                    created_at: current_time_ticks,
+                    owner,
+                };
+                let content_stats = ContentStats {
+                    last_accessed: current_time_ticks,
+                    ..ContentStats::default()
+                };
+                self.state.content_map.insert(&content_id, content_data).expect("Failed to insert content");
+                self.state.content_bytes.insert(&content_id, content).expect("Failed to insert content bytes");
+                self.state.content_stats.insert(&content_id, content_stats).expect("Failed to insert content stats");
+                self.state.replica_sources.insert(&content_id, origin).expect("Failed to record replica source");
+                self.emit(DCDNEvent::ContentUploaded { content_id: content_id.clone(), size, owner });
+            }
+            Message::ContentReplicated { content_id }
+        };
+        self.runtime.prepare_message(message).with_authentication().send_to(origin);
+    }
+
+    async fn receive_replication_answer(&mut self, origin: ChainId, content_id: String, failure: Option<String>) {
+        let replications = self.state.replications.load_entry_mut(&content_id).await.expect("Failed to load replications");
+        let Some(mut replication) = replications.get(&origin).await.unwrap() else {
+            return;
+        };
+        if replication.status != ReplicationStatus::Pending {
+            return;
+        }
+        replication.status = if failure.is_none() { ReplicationStatus::Replicated } else { ReplicationStatus::Failed };
+        replication.reason = failure;
+        replications.insert(&origin, replication).expect("Failed to update replication");
+    }
+
+    /// Records or clears a node's copy of some content, keeping the node → content
+    /// reverse index in sync. Only copies the node's operator claimed can be challenged.
+    async fn set_availability(&mut self, content_id: &str, node_id: &str, available: bool, operator_claimed: bool) {
+        let current_time_ticks = self.runtime.system_time().micros();
+
+        if available {
+            let holders = self.state.content_availability.load_entry_mut(content_id).await.expect("Failed to load availability");
+            let (entry, is_new) = match holders.get(node_id).await.unwrap() {
+                Some(mut entry) => {
+                    entry.updated_at = current_time_ticks;
+                    entry.operator_claimed |= operator_claimed;
+                    (entry, false)
+                }
+                None => {
+                    let entry = AvailabilityEntry {
+                        claimed_at: current_time_ticks,
+                        updated_at: current_time_ticks,
+                        operator_claimed,
+                    };
+                    (entry, true)
+                }
+            };
+            holders.insert(node_id, entry).expect("Failed to update availability");
+
+            let contents = self.state.node_contents.load_entry_mut(node_id).await.expect("Failed to load node contents");
+            contents.insert(content_id).expect("Failed to index node content");
+            if is_new {
+                self.emit(DCDNEvent::AvailabilityChanged {
+                    content_id: content_id.to_string(),
+                    node_id: node_id.to_string(),
+                    available: true,
+                });
+            }
+        } else if self.withdraw_copy(content_id, node_id).await {
+            self.record_node_event(node_id, NodeEventKind::AvailabilityFlap).await;
+        }
+    }
+
+    /// Withdraws a node's copy on the network's behalf, as when content is deleted, purged or
+    /// taken down. The node no longer has to prove it holds the content, so its pending
+    /// challenges for it are cancelled.
+    async fn drop_copy(&mut self, content_id: &str, node_id: &str) {
+        self.withdraw_copy(content_id, node_id).await;
+        let challenge_ids = match self.state.pending_challenges.try_load_entry(node_id).await.unwrap() {
+            Some(pending) => pending.indices().await.unwrap(),
+            None => return,
+        };
+        let mut cancelled = Vec::new();
+        for challenge_id in challenge_ids {
+            if let Some(challenge) = self.state.challenges.get(&challenge_id).await.unwrap() {
+                if challenge.content_id == content_id {
+                    cancelled.push(challenge_id);
+                }
+            }
+        }
+        if cancelled.is_empty() {
+            return;
+        }
+        let pending = self.state.pending_challenges.load_entry_mut(node_id).await.expect("Failed to load pending challenges");
+        for challenge_id in &cancelled {
+            pending.remove(challenge_id).expect("Failed to untrack challenge");
+        }
+        for challenge_id in &cancelled {
+            self.state.challenges.remove(challenge_id).expect("Failed to remove challenge");
+        }
+    }
+
+    /// Removes a node's copy of some content from both availability indexes, returning
+    /// whether the node had claimed one
+    async fn withdraw_copy(&mut self, content_id: &str, node_id: &str) -> bool {
+        let mut withdrawn = false;
+        if self.state.content_availability.try_load_entry(content_id).await.unwrap().is_some() {
+            let holders = self.state.content_availability.load_entry_mut(content_id).await.expect("Failed to load availability");
+            if holders.contains_key(node_id).await.unwrap() {
+                holders.remove(node_id).expect("Failed to update availability");
+                withdrawn = true;
+            }
+        }
+        if self.state.node_contents.try_load_entry(node_id).await.unwrap().is_some() {
+            let contents = self.state.node_contents.load_entry_mut(node_id).await.expect("Failed to load node contents");
+            contents.remove(content_id).expect("Failed to unindex node content");
+        }
+        if withdrawn {
+            self.emit(DCDNEvent::AvailabilityChanged {
+                content_id: content_id.to_string(),
+                node_id: node_id.to_string(),
+                available: false,
+            });
+        }
+        withdrawn
+    }
+
+    async fn register_node(
+        &mut self,
+        node_id: String,
+        location: NodeLocation,
+        capacity: u64,
+        bandwidth: u64,
+        profile: NodeProfile,
+        stake: Amount,
+    ) -> DCDNResponse {
+        if let Err(message) = location.validate().and_then(|()| profile.validate()) {
+            return DCDNResponse::Error { message };
+        }
+        if capacity == 0 {
+            return DCDNResponse::Error {
+                message: "Capacity must be positive".to_string()
+            };
+        }
+
+        // Check if node already exists
+        if self.state.nodes.get(&node_id).await.unwrap().is_some() {
+            return DCDNResponse::Error { 
+                message: "Node already registered".to_string() 
+            };
+        }
+
+        let Some(operator) = self.runtime.authenticated_signer() else {
+            return DCDNResponse::Error {
+                message: "Node registration must be signed by the operator".to_string()
+            };
+        };
+        if self.state.pauses.get().registrations {
+            return DCDNResponse::Error {
+                message: "Node registrations are paused".to_string()
+            };
+        }
+        let config = self.config();
+        if config.node_admission == NodeAdmission::Permissioned && !config.admins.contains(&operator) {
+            return DCDNResponse::Error {
+                message: "Only admins may register nodes on this network".to_string()
+            };
+        }
+        if stake < config.min_node_stake {
+            return DCDNResponse::Error {
+                message: format!("Stake must be at least {}", config.min_node_stake)
+            };
+        }
+        if self.runtime.owner_balance(operator) < stake {
+            return DCDNResponse::Error {
+                message: "Insufficient balance for stake".to_string()
+            };
+        }
+        let application_account = self.application_account();
+        self.runtime.transfer(operator, application_account, stake);
+
+        let registration = NodeRegistration { node_id, operator, location, capacity, bandwidth, profile, stake };
+        let home_chain = self.runtime.chain_id();
+        self.add_node(registration.clone(), home_chain).await;
+        if !self.is_registry() {
+            self.send_to_registry(Message::NodeRegistered { registration });
+        }
+
+        DCDNResponse::NodeRegistered
+    }
+
+    /// Stores a new node, counting it in the network totals if this is the registry chain
+    async fn add_node(&mut self, registration: NodeRegistration, home_chain: ChainId) {
+        let NodeRegistration { node_id, operator, location, capacity, bandwidth, profile, stake } = registration;
+        self.emit(DCDNEvent::NodeRegistered {
+            node_id: node_id.clone(),
+            operator,
+            home_chain,
+            region: location.region.clone(),
+            capacity,
+        });
+        let current_time_ticks = self.runtime.system_time().micros();
+        let node_info = NodeInfo {
+            id: node_id.clone(),
+            operator,
+            home_chain,
+            stake,
+            unbonding_until: None,
+            location,
+            capacity,
+            used_capacity: 0,
+            available: true,
+            created_at: current_time_ticks,
+            data_served: 0,
+            bandwidth,
+            profile,
+            last_heartbeat: current_time_ticks,
+            window_start: current_time_ticks,
+            window_bytes: 0,
+            reported_until: current_time_ticks,
+            earned: Amount::ZERO,
+        };
+        self.state.nodes.insert(&node_id, node_info).expect("Failed to insert node");
+        self.record_node_event(&node_id, NodeEventKind::Heartbeat).await;
+
+        if self.is_registry() {
+            let count = *self.state.node_count.get();
+            self.state.node_count.set(count + 1);
+
+            let total = *self.state.total_capacity.get();
+            self.state.total_capacity.set(total.saturating_add(capacity));
+        }
+    }
+
+    /// Mirrors a node registered on another chain. The first chain to register a node ID
+    /// keeps it, unless it deregistered the node since; other chains are told to refund.
+    async fn receive_node_registration(&mut self, origin: ChainId, registration: NodeRegistration) {
+        let taken = match self.state.nodes.get(&registration.node_id).await.unwrap() {
+            Some(node_info) => node_info.home_chain != origin || node_info.unbonding_until.is_none(),
+            None => false,
+        };
+        if taken {
+            let message = Message::NodeRejected { node_id: registration.node_id };
+            self.runtime.prepare_message(message).with_authentication().send_to(origin);
+            return;
+        }
+        self.add_node(registration, origin).await;
+    }
+
+    /// Takes a node the registry refused out of the network, letting its operator withdraw
+    /// the stake right away
+    async fn receive_node_rejection(&mut self, node_id: String) {
+        let this_chain = self.runtime.chain_id();
+        let Some(node_info) = self.state.nodes.get(&node_id).await.unwrap() else {
+            return;
+        };
+        if node_info.home_chain != this_chain || node_info.unbonding_until.is_some() {
+            return;
+        }
+        self.retire_node(node_info).await;
+        let current_time_ticks = self.runtime.system_time().micros();
+        if let Some(node_info) = self.state.nodes.get_mut(&node_id).await.unwrap() {
+            node_info.unbonding_until = Some(current_time_ticks);
+        }
+    }
+
+    /// Loads the registry's record of a node, if the message comes from the node's own chain
+    async fn registered_node(&mut self, origin: ChainId, node_id: &str) -> Option<NodeInfo> {
+        if !self.is_registry() {
+            return None;
+        }
+        self.state.nodes.get(node_id).await.unwrap().filter(|node_info| node_info.home_chain == origin)
+    }
+
+    /// Chain keeping the network-wide node registry
+    fn registry_chain(&mut self) -> ChainId {
+        match self.runtime.application_parameters().registry_chain {
+            Some(registry_chain) => registry_chain,
+            None => self.runtime.application_creator_chain_id(),
+        }
+    }
+
+    fn is_registry(&mut self) -> bool {
+        self.runtime.chain_id() == self.registry_chain()
+    }
+
+    fn send_to_registry(&mut self, message: Message) {
+        let registry_chain = self.registry_chain();
+        self.runtime.prepare_message(message).with_authentication().send_to(registry_chain);
+    }
+
+    async fn update_node(&mut self, node_id: String, profile: NodeProfile) -> DCDNResponse {
+        if let Err(message) = profile.validate() {
+            return DCDNResponse::Error { message };
+        }
+
+        match self.operated_node(&node_id).await {
+            Ok(mut node_info) => {
+                node_info.profile = profile;
+                self.state.nodes.insert(&node_id, node_info).expect("Failed to update node");
+                DCDNResponse::NodeUpdated
+            }
+            Err(response) => response,
+        }
+    }
+
+    async fn heartbeat(&mut self, node_id: String) -> DCDNResponse {
+        match self.operated_node(&node_id).await {
+            Ok(mut node_info) => {
+                node_info.last_heartbeat = self.runtime.system_time().micros();
+                self.state.nodes.insert(&node_id, node_info).expect("Failed to update node");
+                self.record_node_event(&node_id, NodeEventKind::Heartbeat).await;
+                if !self.is_registry() {
+                    self.send_to_registry(Message::NodeHeartbeat { node_id });
+                }
+                DCDNResponse::HeartbeatRecorded
+            }
+            Err(response) => response,
+        }
+    }
+
+    async fn update_capacity(&mut self, node_id: String, capacity: u64) -> DCDNResponse {
+        let node_info = match self.operated_node(&node_id).await {
+            Ok(node_info) => node_info,
+            Err(response) => return response,
+        };
+        if capacity == 0 {
+            return DCDNResponse::Error {
+                message: "Capacity must be positive".to_string()
+            };
+        }
+
+        self.set_capacity(node_info, capacity).await;
+        if !self.is_registry() {
+            self.send_to_registry(Message::NodeCapacityChanged { node_id, capacity });
+        }
+
+        DCDNResponse::NodeUpdated
+    }
+
+    /// Changes a node's capacity, keeping the network total in step on the registry chain
+    async fn set_capacity(&mut self, mut node_info: NodeInfo, capacity: u64) {
+        if self.is_registry() {
+            let total = *self.state.total_capacity.get();
+            self.state.total_capacity.set(total.saturating_sub(node_info.capacity).saturating_add(capacity));
+        }
+        node_info.capacity = capacity;
+        node_info.used_capacity = node_info.used_capacity.min(capacity);
+        self.state.nodes.insert(&node_info.id.clone(), node_info).expect("Failed to update node");
+    }
+
+    async fn deregister_node(&mut self, node_id: String) -> DCDNResponse {
+        let node_info = match self.operated_node(&node_id).await {
+            Ok(node_info) => node_info,
+            Err(response) => return response,
+        };
+
+        let withdrawable_at = self.retire_node(node_info).await;
+        if !self.is_registry() {
+            self.send_to_registry(Message::NodeDeregistered { node_id });
+        }
+
+        DCDNResponse::NodeDeregistered { withdrawable_at }
+    }
+
+    /// Takes a node out of the network and starts its unbonding period, returning when
+    /// its stake can be withdrawn
+    async fn retire_node(&mut self, mut node_info: NodeInfo) -> u64 {
+        let node_id = node_info.id.clone();
+
+        // Stop advertising the node's copies before it leaves
+        let content_ids = match self.state.node_contents.try_load_entry(&node_id).await.unwrap() {
+            Some(contents) => contents.indices().await.unwrap(),
+            None => Vec::new(),
+        };
+        for content_id in content_ids {
+            let holders = self.state.content_availability.load_entry_mut(&content_id).await.expect("Failed to load availability");
+            holders.remove(&node_id).expect("Failed to update availability");
+            self.emit(DCDNEvent::AvailabilityChanged { content_id, node_id: node_id.clone(), available: false });
+        }
+        self.state.node_contents.remove_entry(&node_id).expect("Failed to clear node contents");
+
+        let withdrawable_at = self.runtime.system_time().micros() + UNBONDING_PERIOD_MICROS;
+        node_info.available = false;
+        node_info.unbonding_until = Some(withdrawable_at);
+        let capacity = node_info.capacity;
+        self.state.nodes.insert(&node_id, node_info).expect("Failed to update node");
+
+        if self.is_registry() {
+            let count = *self.state.node_count.get();
+            self.state.node_count.set(count.saturating_sub(1));
+
+            let total = *self.state.total_capacity.get();
+            self.state.total_capacity.set(total.saturating_sub(capacity));
+        }
+
+        withdrawable_at
+    }
+
+    async fn withdraw_stake(&mut self, node_id: String) -> DCDNResponse {
+        let Some(node_info) = self.state.nodes.get(&node_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Node does not exist".to_string()
+            };
+        };
+        if self.runtime.authenticated_signer() != Some(node_info.operator) {
+            return DCDNResponse::Error {
+                message: "Only the node operator can withdraw its stake".to_string()
+            };
+        }
+        // The registry's copy of a node registered elsewhere holds no stake
+        if node_info.home_chain != self.runtime.chain_id() {
+            return DCDNResponse::Error {
+                message: "Stake can only be withdrawn on the node's own chain".to_string()
+            };
+        }
+        match node_info.unbonding_until {
+            Some(withdrawable_at) if withdrawable_at <= self.runtime.system_time().micros() => {}
+            Some(_) => {
+                return DCDNResponse::Error {
+                    message: "Stake is still unbonding".to_string()
+                };
+            }
+            None => {
+                return DCDNResponse::Error {
+                    message: "Node must be deregistered before withdrawing stake".to_string()
+                };
+            }
+        }
+
+        let application_account = self.application_account();
+        let operator_account = Account::new(application_account.chain_id, node_info.operator);
+        if node_info.stake > Amount::ZERO {
+            self.runtime.transfer(application_account.owner, operator_account, node_info.stake);
+        }
+
+        self.state.nodes.remove(&node_id).expect("Failed to remove node");
+        self.state.node_events.remove_entry(&node_id).expect("Failed to remove node events");
+        self.state.failure_reports.remove_entry(&node_id).expect("Failed to remove failure reports");
+        if let Some(pending) = self.state.pending_challenges.try_load_entry(&node_id).await.unwrap() {
+            for challenge_id in pending.indices().await.unwrap() {
+                self.state.challenges.remove(&challenge_id).expect("Failed to remove challenge");
+            }
+        }
+        self.state.pending_challenges.remove_entry(&node_id).expect("Failed to remove pending challenges");
+        self.state.last_challenged.remove(&node_id).expect("Failed to remove challenge time");
+
+        DCDNResponse::StakeWithdrawn { amount: node_info.stake }
+    }
+
+    /// Confiscates part of a node's stake. A node whose stake drops below the minimum
+    /// is taken offline until it deregisters.
+    async fn slash_node(&mut self, node_id: &str, reason: SlashReason) {
+        let min_node_stake = self.config().min_node_stake;
+        let Some(node_info) = self.state.nodes.get_mut(node_id).await.unwrap() else {
+            return;
+        };
+        let percent = match reason {
+            SlashReason::FailedAudit => FAILED_AUDIT_SLASH_PERCENT,
+            SlashReason::InflatedUsage => INFLATED_USAGE_SLASH_PERCENT,
+        };
+        let penalty = Amount::from_attos(u128::from(node_info.stake) * percent / 100);
+        node_info.stake = node_info.stake.saturating_sub(penalty);
+        if node_info.stake < min_node_stake {
+            node_info.available = false;
+        }
+
+        let slashed = *self.state.slashed_stake.get();
+        self.state.slashed_stake.set(slashed.saturating_add(penalty));
+    }
+
+    /// Loads a live node operated on this chain, checking that the operation is signed by
+    /// its operator
+    async fn operated_node(&mut self, node_id: &str) -> Result<NodeInfo, DCDNResponse> {
+        let Some(node_info) = self.state.nodes.get(node_id).await.unwrap() else {
+            return Err(DCDNResponse::Error {
+                message: "Node does not exist".to_string()
+            });
+        };
+        if self.runtime.authenticated_signer() != Some(node_info.operator) {
+            return Err(DCDNResponse::Error {
+                message: "Operation must be signed by the node operator".to_string()
+            });
+        }
+        if node_info.home_chain != self.runtime.chain_id() {
+            return Err(DCDNResponse::Error {
+                message: "Node is operated from another chain".to_string()
+            });
+        }
+        if node_info.unbonding_until.is_some() {
+            return Err(DCDNResponse::Error {
+                message: "Node has been deregistered".to_string()
+            });
+        }
+        Ok(node_info)
+    }
+
+    /// The application's own account on this chain, which holds node stakes
+    fn application_account(&mut self) -> Account {
+        Account::new(self.runtime.chain_id(), AccountOwner::from(self.runtime.application_id()))
+    }
+
+    async fn report_node_failure(&mut self, node_id: String, content_id: String) -> DCDNResponse {
+        let Some(reporter) = self.runtime.authenticated_signer() else {
+            return DCDNResponse::Error {
+                message: "Failure reports must be signed by the client".to_string()
+            };
+        };
+        if !self.state.nodes.contains_key(&node_id).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "Node does not exist".to_string()
+            };
+        }
+        if !self.state.content_map.contains_key(&content_id).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "Content does not exist".to_string()
+            };
+        }
+
+        // Each client counts once per interval, however often it reports
+        let current_time_ticks = self.runtime.system_time().micros();
+        let reporters = self.state.failure_reports.load_entry_mut(&node_id).await.expect("Failed to load failure reports");
+        if let Some(reported_at) = reporters.get(&reporter).await.unwrap() {
+            if current_time_ticks.saturating_sub(reported_at) < FAILURE_REPORT_INTERVAL_MICROS {
+                return DCDNResponse::Error {
+                    message: "A failure of this node was already reported recently".to_string()
+                };
+            }
+        }
+        reporters.insert(&reporter, current_time_ticks).expect("Failed to record failure report");
+
+        self.record_node_event(&node_id, NodeEventKind::ClientFailure).await;
+        DCDNResponse::FailureReported
+    }
+
+    /// Appends to the history used to compute a node's reliability score, dropping events
+    /// older than any reliability window reaches back
+    async fn record_node_event(&mut self, node_id: &str, kind: NodeEventKind) {
+        let timestamp = self.runtime.system_time().micros();
+        let horizon = timestamp.saturating_sub(MAX_RELIABILITY_WINDOW_MICROS);
+        let events = self.state.node_events.load_entry_mut(node_id).await.expect("Failed to load node events");
+        while let Some(event) = events.front().await.unwrap() {
+            if event.timestamp >= horizon {
+                break;
+            }
+            events.delete_front();
+        }
+        events.push_back(NodeEvent { kind, timestamp });
+    }
+
+    async fn issue_challenge(&mut self, content_id: String, node_id: String) -> DCDNResponse {
+        let Some(content_data) = self.state.content_map.get(&content_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Content does not exist".to_string()
+            };
+        };
+        let signer = self.runtime.authenticated_signer();
+        if signer.is_none() || (signer != content_data.owner && self.signing_admin().is_none()) {
+            return DCDNResponse::Error {
+                message: "Only the content owner or an admin can challenge its nodes".to_string()
+            };
+        }
+        match self.state.nodes.get(&node_id).await.unwrap() {
+            Some(node_info) if node_info.home_chain == self.runtime.chain_id() => {}
+            _ => {
+                return DCDNResponse::Error {
+                    message: "Node is not operated on this chain".to_string()
+                };
+            }
+        }
+        // A copy listed by someone else's cache request is not the operator's claim to answer for
+        let entry = match self.state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders.get(&node_id).await.unwrap(),
+            None => None,
+        };
+        if !entry.is_some_and(|entry| entry.operator_claimed) {
+            return DCDNResponse::Error {
+                message: "Node's operator has not claimed this content".to_string()
+            };
+        }
+        let issued_at = self.runtime.system_time().micros();
+        if let Some(challenged_at) = self.state.last_challenged.get(&node_id).await.unwrap() {
+            if issued_at.saturating_sub(challenged_at) < CHALLENGE_INTERVAL_MICROS {
+                return DCDNResponse::Error {
+                    message: "Node was challenged recently".to_string()
+                };
+            }
+        }
+        let Some(content) = self.state.content_bytes.get(&content_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Content does not exist".to_string()
+            };
+        };
+
+        let challenge_id = *self.state.next_challenge_id.get();
+        self.state.next_challenge_id.set(challenge_id + 1);
+
+        // Derive the nonce from chain state so neither the challenger nor the node can pick it
+        let mut hasher = Sha256::new();
+        hasher.update(self.runtime.chain_id().to_string().as_bytes());
+        hasher.update(self.runtime.block_height().0.to_le_bytes());
+        hasher.update(challenge_id.to_le_bytes());
+        hasher.update(content_id.as_bytes());
+        hasher.update(node_id.as_bytes());
+        let nonce = hasher.finalize();
+
+        let chunk_count = (content.len() as u64).div_ceil(CHALLENGE_CHUNK_SIZE).max(1);
+        let seed = u64::from_le_bytes(nonce[..8].try_into().expect("SHA-256 output is 32 bytes"));
+        let chunk_index = seed % chunk_count;
+        let nonce = hex::encode(nonce);
+
+        let challenge = StorageChallenge {
+            id: challenge_id,
+            content_id,
+            node_id: node_id.clone(),
+            chunk_index,
+            nonce: nonce.clone(),
+            issued_at,
+            deadline: issued_at + CHALLENGE_RESPONSE_WINDOW_MICROS,
+        };
+        self.state.challenges.insert(&challenge_id, challenge).expect("Failed to store challenge");
+        self.state.last_challenged.insert(&node_id, issued_at).expect("Failed to record challenge time");
+        let pending = self.state.pending_challenges.load_entry_mut(&node_id).await.expect("Failed to load pending challenges");
+        pending.insert(&challenge_id).expect("Failed to track challenge");
+
+        DCDNResponse::ChallengeIssued { challenge_id, chunk_index, nonce }
+    }
+
+    async fn respond_challenge(&mut self, challenge_id: u64, chunk_hash: String, proof: String) -> DCDNResponse {
+        let Some(challenge) = self.state.challenges.get(&challenge_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Challenge does not exist or has been resolved".to_string()
+            };
+        };
+        if let Err(response) = self.operated_node(&challenge.node_id).await {
+            return response;
+        }
+
+        let on_time = self.runtime.system_time().micros() <= challenge.deadline;
+        let passed = on_time && self.verify_challenge_response(&challenge, &chunk_hash, &proof).await;
+        self.resolve_challenge(challenge, passed).await;
+
+        if passed {
+            DCDNResponse::ChallengePassed
+        } else {
+            DCDNResponse::ChallengeFailed
+        }
+    }
+
+    async fn expire_challenge(&mut self, challenge_id: u64) -> DCDNResponse {
+        let Some(challenge) = self.state.challenges.get(&challenge_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Challenge does not exist or has been resolved".to_string()
+            };
+        };
+        if self.runtime.system_time().micros() <= challenge.deadline {
+            return DCDNResponse::Error {
+                message: "Challenge deadline has not passed".to_string()
+            };
+        }
+
+        self.resolve_challenge(challenge, false).await;
+        DCDNResponse::ChallengeFailed
+    }
+
+    /// Checks a response against the chunk of the stored content the challenge asked for
+    async fn verify_challenge_response(&self, challenge: &StorageChallenge, chunk_hash: &str, proof: &str) -> bool {
+        let Some(content) = self.state.content_bytes.get(&challenge.content_id).await.unwrap() else {
+            return false;
+        };
+        let start = (challenge.chunk_index * CHALLENGE_CHUNK_SIZE).min(content.len() as u64) as usize;
+        let end = (start + CHALLENGE_CHUNK_SIZE as usize).min(content.len());
+        let chunk = &content[start..end];
+
+        let expected_chunk_hash = format!("{:x}", Sha256::digest(chunk));
+        let mut hasher = Sha256::new();
+        hasher.update(hex::decode(&challenge.nonce).expect("Challenge nonces are hex-encoded"));
+        hasher.update(chunk);
+        let expected_proof = format!("{:x}", hasher.finalize());
+
+        chunk_hash.eq_ignore_ascii_case(&expected_chunk_hash) && proof.eq_ignore_ascii_case(&expected_proof)
+    }
+
+    /// Records a challenge outcome in the node's history and forgets the challenge. A failing
+    /// node loses its listing for the content and part of its stake.
+    async fn resolve_challenge(&mut self, challenge: StorageChallenge, passed: bool) {
+        let StorageChallenge { id: challenge_id, content_id, node_id, .. } = challenge;
+        self.state.challenges.remove(&challenge_id).expect("Failed to remove challenge");
+        let pending = self.state.pending_challenges.load_entry_mut(&node_id).await.expect("Failed to load pending challenges");
+        pending.remove(&challenge_id).expect("Failed to untrack challenge");
+
+        if passed {
+            self.record_node_event(&node_id, NodeEventKind::AuditPassed).await;
+        } else {
+            self.record_node_event(&node_id, NodeEventKind::AuditFailed).await;
+            self.withdraw_copy(&content_id, &node_id).await;
+            self.slash_node(&node_id, SlashReason::FailedAudit).await;
+        }
+    }
+
+    async fn report_usage(&mut self, node_id: String, receipts: Vec<SignedReceipt>) -> DCDNResponse {
+        // Check the node exists and the report comes from its operator
+        let node_info = match self.operated_node(&node_id).await {
+            Ok(node_info) => node_info,
+            Err(response) => return response,
+        };
+        if receipts.is_empty() {
+            return DCDNResponse::Error {
+                message: "Usage reports must include at least one receipt".to_string()
+            };
+        }
+
+        // Validate every receipt before crediting any of them
+        let chain_id = self.runtime.chain_id();
+        let application_id = self.runtime.application_id().forget_abi();
+        let mut claimed_receipts = BTreeSet::new();
+        let mut claimed_downloads = BTreeMap::<(AccountOwner, String), u64>::new();
+        let mut usage_by_content = BTreeMap::<String, UsageRow>::new();
+        for signed_receipt in &receipts {
+            let client = match signed_receipt.verify(chain_id, application_id) {
+                Ok(client) => client,
+                Err(message) => return DCDNResponse::Error { message },
+            };
+            if client == node_info.operator {
+                return DCDNResponse::Error {
+                    message: "Receipts cannot be signed by the node's operator".to_string()
+                };
+            }
+            let receipt = &signed_receipt.receipt;
+            if receipt.node_id != node_id {
+                return DCDNResponse::Error {
+                    message: "Receipt was issued to a different node".to_string()
+                };
+            }
+            let Some(content_data) = self.state.content_map.get(&receipt.content_id).await.unwrap() else {
+                return DCDNResponse::Error { 
+                    message: "Content does not exist".to_string() 
+                };
+            };
+            // Serving quarantined or taken down content earns nothing
+            if let Some(response) = self.blocked_content(&receipt.content_id).await {
+                return response;
+            }
+            if receipt.bytes > content_data.metadata.size {
+                return DCDNResponse::Error {
+                    message: "Receipt claims more bytes than the content's size".to_string()
+                };
+            }
+            let receipt_key = (client, receipt.nonce);
+            if self.state.used_receipts.contains(&receipt_key).await.unwrap() || !claimed_receipts.insert(receipt_key) {
+                return DCDNResponse::Error {
+                    message: "Receipt has already been claimed".to_string()
+                };
+            }
+            // Each receipt must be backed by a download the client signed on chain
+            let download_key = (client, receipt.content_id.clone());
+            let claimed = claimed_downloads.entry(download_key.clone()).or_default();
+            *claimed += 1;
+            if *claimed > self.state.downloads.get(&download_key).await.unwrap().unwrap_or_default() {
+                return DCDNResponse::Error {
+                    message: "Receipt's client has no unclaimed download of this content".to_string()
+                };
+            }
+            let row = usage_by_content.entry(receipt.content_id.clone()).or_insert_with(|| UsageRow {
+                content_id: receipt.content_id.clone(),
+                bytes: 0,
+                request_count: 0,
+            });
+            row.bytes += receipt.bytes;
+            row.request_count += 1;
+        }
+
+        // Receipts count against the node's rolling usage window
+        let current_time_ticks = self.runtime.system_time().micros();
+        let window_bytes = if current_time_ticks.saturating_sub(node_info.window_start) < USAGE_WINDOW_MICROS {
+            node_info.window_bytes
+        } else {
+            0
+        };
+        let rows = usage_by_content.into_values().collect::<Vec<_>>();
+        let response = self.submit_usage(&node_info, rows, true, window_bytes, USAGE_WINDOW_MICROS).await;
+        if !matches!(response, DCDNResponse::Error { .. }) {
+            for receipt_key in &claimed_receipts {
+                self.state.used_receipts.insert(receipt_key).expect("Failed to record receipt");
+            }
+            for (download_key, claimed) in claimed_downloads {
+                let remaining = self.state.downloads.get(&download_key).await.unwrap().unwrap_or_default() - claimed;
+                if remaining == 0 {
+                    self.state.downloads.remove(&download_key).expect("Failed to update downloads");
+                } else {
+                    self.state.downloads.insert(&download_key, remaining).expect("Failed to update downloads");
+                }
+            }
+        }
+        response
+    }
+
+    async fn report_usage_batch(&mut self, node_id: String, window_start: u64, window_end: u64, rows: Vec<UsageRow>) -> DCDNResponse {
+        // Check the node exists and the report comes from its operator
+        let node_info = match self.operated_node(&node_id).await {
+            Ok(node_info) => node_info,
+            Err(response) => return response,
+        };
+        if window_start >= window_end || window_end > self.runtime.system_time().micros() {
+            return DCDNResponse::Error {
+                message: "Usage window must be non-empty and in the past".to_string()
+            };
+        }
+        if window_start < node_info.reported_until {
+            return DCDNResponse::Error {
+                message: "Usage window overlaps one already reported".to_string()
+            };
+        }
+        if rows.is_empty() {
+            return DCDNResponse::Error {
+                message: "Usage batches must include at least one row".to_string()
+            };
+        }
+
+        // Validate every row before crediting any of them
+        let mut content_ids = BTreeSet::new();
+        for row in &rows {
+            if !content_ids.insert(&row.content_id) {
+                return DCDNResponse::Error {
+                    message: "Usage batch lists the same content more than once".to_string()
+                };
+            }
+            let Some(content_data) = self.state.content_map.get(&row.content_id).await.unwrap() else {
+                return DCDNResponse::Error { 
+                    message: "Content does not exist".to_string() 
+                };
+            };
+            if let Some(response) = self.blocked_content(&row.content_id).await {
+                return response;
+            }
+            if let Some(response) = self.expired_content(&row.content_id).await {
+                return response;
+            }
+            if row.bytes > row.request_count.saturating_mul(content_data.metadata.size) {
+                return DCDNResponse::Error {
+                    message: "Usage row claims more bytes than its requests could have served".to_string()
+                };
+            }
+        }
+
+        let response = self.submit_usage(&node_info, rows, false, 0, window_end - window_start).await;
+        if !matches!(response, DCDNResponse::Error { .. }) {
+            if let Some(mut node_info) = self.state.nodes.get(&node_id).await.unwrap() {
+                node_info.reported_until = window_end;
+                self.state.nodes.insert(&node_id, node_info).expect("Failed to update node");
+            }
+        }
+        response
+    }
+
+    /// Credits validated usage, paying from escrow only if it is backed by `receipted` client
+    /// receipts, unless it looks implausible, in which case it is queued for review; a node
+    /// claiming to have served content it does not hold is slashed instead. `prior_bytes` were already served during
+    /// the `elapsed_micros` the report covers.
+    async fn submit_usage(
+        &mut self,
+        node_info: &NodeInfo,
+        rows: Vec<UsageRow>,
+        receipted: bool,
+        prior_bytes: u64,
+        elapsed_micros: u64,
+    ) -> DCDNResponse {
+        // Serving content the node never claimed to hold means the report is inflated
+        for row in &rows {
+            let holds_content = match self.state.node_contents.try_load_entry(&node_info.id).await.unwrap() {
+                Some(contents) => contents.contains(&row.content_id).await.unwrap(),
+                None => false,
+            };
+            if !holds_content {
+                self.slash_node(&node_info.id, SlashReason::InflatedUsage).await;
+                return DCDNResponse::Error {
+                    message: "Node does not hold this content; stake slashed".to_string()
+                };
+            }
+        }
+
+        let bytes = rows.iter().try_fold(0u64, |total, row| total.checked_add(row.bytes));
+        let Some((bytes, window_bytes)) = bytes.and_then(|bytes| Some((bytes, prior_bytes.checked_add(bytes)?))) else {
+            return DCDNResponse::Error {
+                message: "Usage would overflow the served totals".to_string()
+            };
+        };
+        let current_time_ticks = self.runtime.system_time().micros();
+        let anomaly = usage_anomaly(node_info, window_bytes, bytes, elapsed_micros, current_time_ticks);
+        let reason = match anomaly {
+            Some(reason) => reason,
+            None => {
+                if let Err(message) = self.credit_usage(&node_info.id, &rows, receipted).await {
+                    return DCDNResponse::Error { message };
+                }
+                return DCDNResponse::UsageReported;
+            }
+        };
+
+        let report_id = *self.state.next_flagged_report_id.get();
+        self.state.next_flagged_report_id.set(report_id + 1);
+        let report = FlaggedReport {
+            id: report_id,
+            node_id: node_info.id.clone(),
+            rows,
+            receipted,
+            reason,
+            flagged_at: current_time_ticks,
+            status: ReviewStatus::Pending,
+        };
+        self.state.flagged_reports.insert(&report_id, report).expect("Failed to flag report");
+        self.state.pending_reviews.insert(&report_id).expect("Failed to queue report for review");
+        DCDNResponse::UsageFlagged { report_id }
+    }
+
+    async fn review_usage_report(&mut self, report_id: u64, approve: bool) -> DCDNResponse {
+        if self.signing_admin().is_none() {
+            return DCDNResponse::Error {
+                message: "Only admins may review usage reports".to_string()
+            };
+        }
+        let Some(mut report) = self.state.flagged_reports.get(&report_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Flagged report does not exist".to_string()
+            };
+        };
+        if report.status != ReviewStatus::Pending {
+            return DCDNResponse::Error {
+                message: "Report has already been reviewed".to_string()
+            };
+        }
+
+        if approve {
+            if let Err(message) = self.credit_usage(&report.node_id, &report.rows, report.receipted).await {
+                return DCDNResponse::Error { message };
+            }
+            report.status = ReviewStatus::Approved;
+        } else {
+            report.status = ReviewStatus::Rejected;
+            self.slash_node(&report.node_id, SlashReason::InflatedUsage).await;
+        }
+        self.state.pending_reviews.remove(&report_id).expect("Failed to dequeue report");
+        self.state.flagged_reports.insert(&report_id, report).expect("Failed to update report");
+        DCDNResponse::UsageReportReviewed
+    }
+
+    /// The admin who signed the operation, if it was signed by one
+    fn signing_admin(&mut self) -> Option<AccountOwner> {
+        let signer = self.runtime.authenticated_signer()?;
+        self.admins().contains(&signer).then_some(signer)
+    }
+
+    /// The configured admins or, in a deployment without any, the owners of the
+    /// application's creator chain when running there
+    fn admins(&mut self) -> Vec<AccountOwner> {
+        let admins = self.config().admins;
+        if !admins.is_empty() || self.runtime.chain_id() != self.runtime.application_creator_chain_id() {
+            return admins;
+        }
+        let owners = self.runtime.chain_ownership().all_owners().copied().collect::<BTreeSet<_>>();
+        owners.into_iter().collect()
+    }
+
+    /// Adds usage to the node, content and global counters and, for `receipted` usage, pays
+    /// the node's operator from each content owner's escrow as far as it reaches. Payments
+    /// accrue to the current epoch and become claimable once it is closed. Usage that would
+    /// overflow any counter is rejected without crediting any of it.
+    async fn credit_usage(&mut self, node_id: &str, rows: &[UsageRow], receipted: bool) -> Result<(), String> {
+        let Some(mut node_info) = self.state.nodes.get(node_id).await.unwrap() else {
+            return Err("Node does not exist".to_string());
+        };
+        let overflow = || "Usage would overflow the served totals".to_string();
+        let current_time_ticks = self.runtime.system_time().micros();
+        let epoch = current_time_ticks / EPOCH_LENGTH_MICROS;
+        let price_per_byte = self.state.pricing.get().price_for(&node_info.location.region);
+
+        // Work out every updated counter before changing any of them
+        let mut content_stats = Vec::new();
+        let mut owners = BTreeMap::<AccountOwner, (Amount, OwnerStatement)>::new();
+        let mut payment = Amount::ZERO;
+        let mut bytes_served = 0u64;
+        let mut requests_served = 0u64;
+        for row in rows {
+            bytes_served = bytes_served.checked_add(row.bytes).ok_or_else(overflow)?;
+            requests_served = requests_served.checked_add(row.request_count).ok_or_else(overflow)?;
+            let mut stats = self.state.content_stats.get(&row.content_id).await.unwrap().unwrap_or_default();
+            stats.bytes_served = stats.bytes_served.checked_add(row.bytes).ok_or_else(overflow)?;
+            stats.requests_served = stats.requests_served.checked_add(row.request_count).ok_or_else(overflow)?;
+            content_stats.push((row.content_id.clone(), stats));
+
+            let owner = match self.state.content_map.get(&row.content_id).await.unwrap() {
+                Some(ContentData { owner: Some(owner), .. }) => owner,
+                _ => continue,
+            };
+            let (balance, statement) = match owners.entry(owner) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let balance = self.state.escrow.get(&owner).await.unwrap().unwrap_or_default();
+                    let statement = match self.state.owner_statements.try_load_entry(&owner).await.unwrap() {
+                        Some(statements) => statements.get(&epoch).await.unwrap().unwrap_or_default(),
+                        None => OwnerStatement::default(),
+                    };
+                    entry.insert((balance, statement))
+                }
+            };
+            let cost = if receipted {
+                price_per_byte.saturating_mul(u128::from(row.bytes)).min(*balance)
+            } else {
+                Amount::ZERO
+            };
+            *balance = balance.saturating_sub(cost);
+            payment = payment.saturating_add(cost);
+            statement.epoch = epoch;
+            statement.bytes_delivered = statement.bytes_delivered.checked_add(row.bytes).ok_or_else(overflow)?;
+            statement.requests = statement.requests.checked_add(row.request_count).ok_or_else(overflow)?;
+            statement.spent = statement.spent.saturating_add(cost);
+        }
+
+        let mut node_statement = match self.state.node_statements.try_load_entry(node_id).await.unwrap() {
+            Some(statements) => statements.get(&epoch).await.unwrap().unwrap_or_default(),
+            None => NodeStatement::default(),
+        };
+        node_statement.epoch = epoch;
+        node_statement.bytes_served = node_statement.bytes_served.checked_add(bytes_served).ok_or_else(overflow)?;
+        node_statement.requests_served = node_statement.requests_served.checked_add(requests_served).ok_or_else(overflow)?;
+        node_statement.earned = node_statement.earned.saturating_add(payment);
+
+        let mut summary = self.state.epochs.get(&epoch).await.unwrap().unwrap_or_default();
+        summary.epoch = epoch;
+        summary.bytes_served = summary.bytes_served.checked_add(bytes_served).ok_or_else(overflow)?;
+        summary.payouts = summary.payouts.saturating_add(payment);
+
+        // Update node's served data
+        if current_time_ticks.saturating_sub(node_info.window_start) >= USAGE_WINDOW_MICROS {
+            node_info.window_start = current_time_ticks;
+            node_info.window_bytes = 0;
+        }
+        node_info.window_bytes = node_info.window_bytes.checked_add(bytes_served).ok_or_else(overflow)?;
+        node_info.data_served = node_info.data_served.checked_add(bytes_served).ok_or_else(overflow)?;
+        node_info.earned = node_info.earned.saturating_add(payment);
+        node_info.used_capacity = node_info.used_capacity.saturating_add(bytes_served).min(node_info.capacity);
+        let total = self.state.total_data_served.get().checked_add(bytes_served).ok_or_else(overflow)?;
+
+        // Every counter fits, so record the usage
+        for (content_id, stats) in content_stats {
+            self.state.content_stats.insert(&content_id, stats).expect("Failed to update content stats");
+        }
+        for (owner, (balance, statement)) in owners {
+            if statement.spent > Amount::ZERO {
+                self.state.escrow.insert(&owner, balance).expect("Failed to update escrow");
+            }
+            let statements = self.state.owner_statements.load_entry_mut(&owner).await.expect("Failed to load owner statements");
+            statements.insert(&epoch, statement).expect("Failed to update owner statement");
+        }
+        let statements = self.state.node_statements.load_entry_mut(node_id).await.expect("Failed to load node statements");
+        statements.insert(&epoch, node_statement).expect("Failed to update node statement");
+        if payment > Amount::ZERO {
+            let epoch_earnings = self.state.epoch_earnings.load_entry_mut(&epoch).await.expect("Failed to load epoch earnings");
+            let earnings = epoch_earnings.get_mut_or_default(&node_info.operator).await.expect("Failed to load earnings");
+            *earnings = earnings.saturating_add(payment);
+        }
+        self.state.epochs.insert(&epoch, summary).expect("Failed to update epoch");
+        self.state.nodes.insert(node_id, node_info).expect("Failed to update node");
+        self.state.total_data_served.set(total);
+        self.emit(DCDNEvent::UsageReported { node_id: node_id.to_string(), bytes: bytes_served, requests: requests_served });
+        Ok(())
+    }
+
+    async fn close_epoch(&mut self, epoch: u64) -> DCDNResponse {
+        if epoch >= self.runtime.system_time().micros() / EPOCH_LENGTH_MICROS {
+            return DCDNResponse::Error {
+                message: "Only past epochs can be closed".to_string()
+            };
+        }
+        let Some(mut summary) = self.state.epochs.get(&epoch).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "No usage was recorded in this epoch".to_string()
+            };
+        };
+        if summary.closed {
+            return DCDNResponse::Error {
+                message: "Epoch is already closed".to_string()
+            };
+        }
+        summary.closed = true;
+        let payouts = summary.payouts;
+        self.state.epochs.insert(&epoch, summary).expect("Failed to close epoch");
+
+        // Make the epoch's earnings claimable
+        if let Some(epoch_earnings) = self.state.epoch_earnings.try_load_entry(&epoch).await.unwrap() {
+            let operator_earnings = epoch_earnings.index_values().await.unwrap();
+            for (operator, amount) in operator_earnings {
+                let earnings = self.state.earnings.get_mut_or_default(&operator).await.expect("Failed to load earnings");
+                *earnings = earnings.saturating_add(amount);
+            }
+        }
+        self.state.epoch_earnings.remove_entry(&epoch).expect("Failed to clear epoch earnings");
+
+        DCDNResponse::EpochClosed { payouts }
+    }
+
+    async fn deposit_escrow(&mut self, amount: Amount) -> DCDNResponse {
+        let Some(owner) = self.runtime.authenticated_signer() else {
+            return DCDNResponse::Error {
+                message: "Escrow deposits must be signed".to_string()
+            };
+        };
+        if self.runtime.owner_balance(owner) < amount {
+            return DCDNResponse::Error {
+                message: "Insufficient balance for deposit".to_string()
+            };
+        }
+        let application_account = self.application_account();
+        self.runtime.transfer(owner, application_account, amount);
+
+        let balance = self.state.escrow.get_mut_or_default(&owner).await.expect("Failed to load escrow");
+        *balance = balance.saturating_add(amount);
+        DCDNResponse::EscrowUpdated { balance: *balance }
+    }
+
+    async fn withdraw_escrow(&mut self, amount: Amount) -> DCDNResponse {
+        let Some(owner) = self.runtime.authenticated_signer() else {
+            return DCDNResponse::Error {
+                message: "Escrow withdrawals must be signed".to_string()
+            };
+        };
+        let balance = self.state.escrow.get(&owner).await.unwrap().unwrap_or_default();
+        let Ok(remaining) = balance.try_sub(amount) else {
+            return DCDNResponse::Error {
+                message: "Insufficient escrow balance".to_string()
+            };
+        };
+
+        let application_account = self.application_account();
+        let owner_account = Account::new(application_account.chain_id, owner);
+        self.runtime.transfer(application_account.owner, owner_account, amount);
+        self.state.escrow.insert(&owner, remaining).expect("Failed to update escrow");
+        DCDNResponse::EscrowUpdated { balance: remaining }
+    }
+
+    /// The error to return for content that is blocklisted or that moderators have quarantined
+    /// or taken down
+    async fn blocked_content(&self, content_id: &str) -> Option<DCDNResponse> {
+        if self.state.blocklist.contains_key(content_id).await.unwrap() {
+            return Some(DCDNResponse::Error {
+                message: "Content is blocklisted".to_string()
+            });
+        }
+        let moderation = self.state.moderation.get(content_id).await.unwrap()?;
+        let message = match moderation.status {
+            ModerationStatus::Active => return None,
+            ModerationStatus::Quarantined => "Content is quarantined",
+            ModerationStatus::TakenDown => "Content has been taken down",
+        };
+        Some(DCDNResponse::Error { message: message.to_string() })
+    }
+
+    /// The error to return for content whose `expires_at` has passed
+    async fn expired_content(&mut self, content_id: &str) -> Option<DCDNResponse> {
+        let content_data = self.state.content_map.get(content_id).await.unwrap()?;
+        let expires_at = content_data.metadata.expires_at?;
+        (self.runtime.system_time().micros() >= expires_at).then(|| DCDNResponse::Error {
+            message: "Content has expired".to_string()
+        })
+    }
+
+    async fn report_content(&mut self, content_id: String, reason: String) -> DCDNResponse {
+        if self.runtime.authenticated_signer().is_none() {
+            return DCDNResponse::Error {
+                message: "Content reports must be signed".to_string()
+            };
+        }
+        if !self.state.content_map.contains_key(&content_id).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "Content not found".to_string()
+            };
+        }
+        if reason.trim().is_empty() {
+            return DCDNResponse::Error {
+                message: "A report must give a reason".to_string()
+            };
+        }
+        if let Some(response) = oversized_reason(&reason) {
+            return response;
+        }
+        let reporter = self.runtime.authenticated_signer().expect("Checked above");
+        let reporters = self.state.open_reports.load_entry_mut(&content_id).await.expect("Failed to load open reports");
+        if reporters.contains(&reporter).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "You have already reported this content".to_string()
+            };
+        }
+        reporters.insert(&reporter).expect("Failed to record reporter");
+        let moderation = self.state.moderation.get_mut_or_default(&content_id).await.expect("Failed to load moderation");
+        moderation.report_count += 1;
+        self.record_moderation(&content_id, ModerationRecordKind::Reported, reason).await;
+        DCDNResponse::ContentReported
+    }
+
+    async fn moderate(&mut self, content_id: String, action: ModerationAction, reason: String) -> DCDNResponse {
+        if !self.signed_by_moderator() {
+            return DCDNResponse::Error {
+                message: "Only moderators may moderate content".to_string()
+            };
+        }
+        if let Some(response) = oversized_reason(&reason) {
+            return response;
+        }
+        if !self.state.content_map.contains_key(&content_id).await.unwrap() {
+            return DCDNResponse::Error {
+                message: "Content not found".to_string()
+            };
+        }
+        let (status, kind) = match action {
+            ModerationAction::Quarantine => (ModerationStatus::Quarantined, ModerationRecordKind::Quarantined),
+            ModerationAction::TakeDown => (ModerationStatus::TakenDown, ModerationRecordKind::TakenDown),
+            ModerationAction::Restore => (ModerationStatus::Active, ModerationRecordKind::Restored),
+        };
+        let moderation = self.state.moderation.get_mut_or_default(&content_id).await.expect("Failed to load moderation");
+        if moderation.status == status {
+            return DCDNResponse::Error {
+                message: format!("Content is already {status:?}")
+            };
+        }
+        moderation.status = status;
+        moderation.appealed = false;
+        moderation.appeal_pending = false;
+        // Every report filed so far has now been acted on
+        self.state.open_reports.remove_entry(&content_id).expect("Failed to clear open reports");
+
+        if status == ModerationStatus::TakenDown {
+            self.withdraw_all_copies(&content_id).await;
+        }
+        self.record_moderation(&content_id, kind, reason).await;
+        DCDNResponse::ModerationUpdated
+    }
+
+    async fn appeal_moderation(&mut self, content_id: String, statement: String) -> DCDNResponse {
+        if let Err(response) = self.owned_content(&content_id).await {
+            return response;
+        }
+        if let Some(response) = oversized_reason(&statement) {
+            return response;
+        }
+        let moderation = self.state.moderation.get_mut_or_default(&content_id).await.expect("Failed to load moderation");
+        if moderation.status == ModerationStatus::Active {
+            return DCDNResponse::Error {
+                message: "Content is not blocked".to_string()
+            };
+        }
+        if moderation.appealed {
+            return DCDNResponse::Error {
+                message: "This action has already been appealed".to_string()
+            };
+        }
+        moderation.appealed = true;
+        moderation.appeal_pending = true;
+        self.record_moderation(&content_id, ModerationRecordKind::Appealed, statement).await;
+        DCDNResponse::AppealFiled
+    }
+
+    async fn resolve_appeal(&mut self, content_id: String, granted: bool, reason: String) -> DCDNResponse {
+        if !self.signed_by_moderator() {
+            return DCDNResponse::Error {
+                message: "Only moderators may resolve appeals".to_string()
+            };
+        }
+        if let Some(response) = oversized_reason(&reason) {
+            return response;
+        }
+        let Some(mut moderation) = self.state.moderation.get(&content_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "No appeal is pending for this content".to_string()
+            };
+        };
+        if !moderation.appeal_pending {
+            return DCDNResponse::Error {
+                message: "No appeal is pending for this content".to_string()
+            };
+        }
+        moderation.appeal_pending = false;
+        self.state.open_reports.remove_entry(&content_id).expect("Failed to clear open reports");
+        let kind = if granted {
+            moderation.status = ModerationStatus::Active;
+            ModerationRecordKind::AppealGranted
+        } else {
+            ModerationRecordKind::AppealDenied
+        };
+        self.state.moderation.insert(&content_id, moderation).expect("Failed to update moderation");
+        self.record_moderation(&content_id, kind, reason).await;
+        DCDNResponse::AppealResolved
+    }
+
+    async fn record_moderation(&mut self, content_id: &str, kind: ModerationRecordKind, reason: String) {
+        let record = ModerationRecord {
+            kind,
+            actor: self.runtime.authenticated_signer(),
+            reason,
+            timestamp: self.runtime.system_time().micros(),
+        };
+        let history = self.state.moderation_history.load_entry_mut(content_id).await.expect("Failed to load moderation history");
+        history.push(record);
+    }
+
+    /// Whether the operation was signed by a moderator or an admin
+    fn signed_by_moderator(&mut self) -> bool {
+        let moderators = self.config().moderators;
+        let admins = self.admins();
+        self.runtime
+            .authenticated_signer()
+            .is_some_and(|signer| moderators.contains(&signer) || admins.contains(&signer))
+    }
+
+    async fn block_hashes(&mut self, hashes: Vec<String>, reason: String) -> DCDNResponse {
+        let Some(admin) = self.signing_admin() else {
+            return DCDNResponse::Error {
+                message: "Only admins may manage the blocklist".to_string()
+            };
+        };
+        let hashes = match normalize_hashes(hashes) {
+            Ok(hashes) => hashes,
+            Err(message) => return DCDNResponse::Error { message },
+        };
+        let added_at = self.runtime.system_time().micros();
+        let mut count = 0;
+        for hash in hashes {
+            if self.state.blocklist.contains_key(&hash).await.unwrap() {
+                continue;
+            }
+            let entry = BlockedHash { hash: hash.clone(), reason: reason.clone(), added_by: admin, added_at };
+            self.state.blocklist.insert(&hash, entry).expect("Failed to block hash");
+            self.withdraw_all_copies(&hash).await;
+            count += 1;
+        }
+        DCDNResponse::BlocklistUpdated { count }
+    }
+
+    async fn unblock_hashes(&mut self, hashes: Vec<String>) -> DCDNResponse {
+        if self.signing_admin().is_none() {
+            return DCDNResponse::Error {
+                message: "Only admins may manage the blocklist".to_string()
+            };
+        }
+        let hashes = match normalize_hashes(hashes) {
+            Ok(hashes) => hashes,
+            Err(message) => return DCDNResponse::Error { message },
+        };
+        let mut count = 0;
+        for hash in hashes {
+            if self.state.blocklist.contains_key(&hash).await.unwrap() {
+                self.state.blocklist.remove(&hash).expect("Failed to unblock hash");
+                count += 1;
+            }
+        }
+        DCDNResponse::BlocklistUpdated { count }
+    }
+
+    async fn update_config(&mut self, config: NetworkConfig) -> DCDNResponse {
+        if self.signing_admin().is_none() {
+            return DCDNResponse::Error {
+                message: "Only admins may update the configuration".to_string()
+            };
+        }
+        if config.economics_differ(&self.config()) {
+            return DCDNResponse::Error {
+                message: "Prices, the minimum stake and admins can only change through a proposal".to_string()
+            };
+        }
+        if let Err(message) = config.validate() {
+            return DCDNResponse::Error { message };
+        }
+        self.state.config.set(Some(config));
+        DCDNResponse::ConfigUpdated
+    }
+
+    async fn set_paused(&mut self, target: PauseTarget, paused: bool) -> DCDNResponse {
+        if self.signing_admin().is_none() {
+            return DCDNResponse::Error {
+                message: "Only admins may pause or unpause".to_string()
+            };
+        }
+        let pauses = self.state.pauses.get_mut();
+        match target {
+            PauseTarget::Uploads => pauses.uploads = paused,
+            PauseTarget::Registrations => pauses.registrations = paused,
+        }
+        DCDNResponse::PauseUpdated
+    }
+
+    async fn propose_change(&mut self, change: EconomicChange) -> DCDNResponse {
+        let Some(proposer) = self.signing_admin() else {
+            return DCDNResponse::Error {
+                message: "Only admins may propose changes".to_string()
+            };
+        };
+        if change.admins.as_ref().is_some_and(Vec::is_empty) {
+            return DCDNResponse::Error {
+                message: "A proposal cannot remove every admin".to_string()
+            };
+        }
+        if let Some(pricing) = &change.pricing {
+            if let Err(message) = pricing.validate() {
+                return DCDNResponse::Error { message };
+            }
+        }
+        if let Err(message) = apply_change(self.config(), &change).validate() {
+            return DCDNResponse::Error { message };
+        }
+
+        let proposal_id = *self.state.next_proposal_id.get();
+        self.state.next_proposal_id.set(proposal_id + 1);
+        let mut proposal = Proposal {
+            id: proposal_id,
+            change,
+            proposer,
+            approvals: vec![proposer],
+            created_at: self.runtime.system_time().micros(),
+            executed_at: None,
+        };
+        // A sole admin's proposal takes effect at once
+        if let Err(message) = self.execute_if_approved(&mut proposal) {
+            return DCDNResponse::Error { message };
+        }
+        self.state.proposals.insert(&proposal_id, proposal).expect("Failed to record proposal");
+        DCDNResponse::ProposalCreated { proposal_id }
+    }
+
+    async fn approve_proposal(&mut self, proposal_id: u64) -> DCDNResponse {
+        let Some(admin) = self.signing_admin() else {
+            return DCDNResponse::Error {
+                message: "Only admins may approve proposals".to_string()
+            };
+        };
+        let Some(mut proposal) = self.state.proposals.get(&proposal_id).await.unwrap() else {
+            return DCDNResponse::Error {
+                message: "Proposal does not exist".to_string()
+            };
+        };
+        if proposal.executed_at.is_some() {
+            return DCDNResponse::Error {
+                message: "Proposal has already taken effect".to_string()
+            };
+        }
+        if proposal.approvals.contains(&admin) {
+            return DCDNResponse::Error {
+                message: "Admin has already approved this proposal".to_string()
+            };
+        }
+        proposal.approvals.push(admin);
+        let executed = match self.execute_if_approved(&mut proposal) {
+            Ok(executed) => executed,
+            Err(message) => return DCDNResponse::Error { message },
+        };
+        self.state.proposals.insert(&proposal_id, proposal).expect("Failed to update proposal");
+        DCDNResponse::ProposalApproved { executed }
+    }
+
+    /// Applies a proposal's change once enough of the current admins have approved it, and
+    /// returns whether it did
+    fn execute_if_approved(&mut self, proposal: &mut Proposal) -> Result<bool, String> {
+        // Approvals from accounts that are no longer admins do not count
+        let admins = self.admins();
+        let approvals = proposal.approvals.iter().filter(|approver| admins.contains(approver)).count();
+        if approvals < required_approvals(admins.len()) {
+            return Ok(false);
+        }
+        let updated = apply_change(self.config(), &proposal.change);
+        updated.validate()?;
+        if let Some(pricing) = &proposal.change.pricing {
+            self.state.pricing.set(pricing.clone());
+        }
+        self.state.config.set(Some(updated));
+        proposal.executed_at = Some(self.runtime.system_time().micros());
+        Ok(true)
+    }
+
+    async fn claim_earnings(&mut self) -> DCDNResponse {
+        let Some(operator) = self.runtime.authenticated_signer() else {
+            return DCDNResponse::Error {
+                message: "Earnings claims must be signed".to_string()
+            };
+        };
+        let amount = self.state.earnings.get(&operator).await.unwrap().unwrap_or_default();
+        if amount == Amount::ZERO {
+            return DCDNResponse::Error {
+                message: "No earnings to claim".to_string()
+            };
+        }
+
+        let application_account = self.application_account();
+        let operator_account = Account::new(application_account.chain_id, operator);
+        self.runtime.transfer(application_account.owner, operator_account, amount);
+        self.state.earnings.remove(&operator).expect("Failed to clear earnings");
+        DCDNResponse::EarningsClaimed { amount }
+    }
+
+    async fn update_metadata(&mut self, content_id: String, mut metadata: ContentMetadata) -> DCDNResponse {
+        let mut content_data = match self.owned_content(&content_id).await {
+            Ok(content_data) => content_data,
+            Err(response) => return response,
+        };
+        // Size and hash describe the stored bytes, and the owner and creation time the upload,
+        // so none of them can be edited
+        metadata.size = content_data.metadata.size;
+        metadata.content_hash = content_data.metadata.content_hash.take();
+        metadata.owner = std::mem::take(&mut content_data.metadata.owner);
+        metadata.created_at = content_data.metadata.created_at;
+        content_data.metadata = metadata;
+        self.state.content_map.insert(&content_id, content_data).expect("Failed to update content");
+        // Replicas carry the old metadata; the next download fetches a fresh copy
+        self.drop_replicas(&content_id).await;
+        DCDNResponse::MetadataUpdated
+    }
+}
+
+/// Lowercases hex-encoded SHA-256 hashes, rejecting the whole list if any is malformed
+fn normalize_hashes(hashes: Vec<String>) -> Result<Vec<String>, String> {
+    hashes
+        .into_iter()
+        .map(|hash| {
+            let hash = hash.trim().to_ascii_lowercase();
+            if hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(hash)
+            } else {
+                Err(format!("Invalid SHA-256 hash: {hash:?}"))
+            }
+        })
+        .collect()
+}
+
+/// Refuses a report reason, moderator's reason or appeal statement longer than
+/// `MAX_REPORT_REASON_LEN` bytes
+fn oversized_reason(reason: &str) -> Option<DCDNResponse> {
+    (reason.len() > MAX_REPORT_REASON_LEN).then(|| DCDNResponse::Error {
+        message: format!("Moderation reasons and appeals may be at most {MAX_REPORT_REASON_LEN} bytes")
+    })
+}
+
+/// The configuration with the settings a proposal changes replaced
+fn apply_change(mut config: NetworkConfig, change: &EconomicChange) -> NetworkConfig {
+    if let Some(pricing) = &change.pricing {
+        config.price_per_byte = pricing.price_per_byte;
+    }
+    if let Some(min_node_stake) = change.min_node_stake {
+        config.min_node_stake = min_node_stake;
+    }
+    if let Some(admins) = &change.admins {
+        config.admins = admins.clone();
+    }
+    config
+}
+
+/// Explains why a claim of `window_bytes` served over `elapsed_micros`, of which `bytes` are
+/// newly reported, needs an admin's review, if it does. Bandwidth is declared by the node
+/// itself, so a node's first report and any report above a fixed size are always reviewed.
+fn usage_anomaly(node_info: &NodeInfo, window_bytes: u64, bytes: u64, elapsed_micros: u64, now: u64) -> Option<String> {
+    let bandwidth_budget = u128::from(node_info.bandwidth) * u128::from(elapsed_micros) / 1_000_000;
+    if u128::from(window_bytes) > bandwidth_budget {
+        return Some(format!(
+            "{window_bytes} bytes in {elapsed_micros} µs exceeds the declared bandwidth of {} bytes/s",
+            node_info.bandwidth
+        ));
+    }
+    if node_info.data_served == 0 {
+        return Some("A node's first usage report is always reviewed".to_string());
+    }
+    if bytes > MAX_UNREVIEWED_USAGE_BYTES {
+        return Some(format!("{bytes} bytes exceeds the {MAX_UNREVIEWED_USAGE_BYTES} bytes a report may credit unreviewed"));
+    }
+
+    // Compare the reported rate with the node's average rate since registration
+    let age = now.saturating_sub(node_info.created_at);
+    if age >= USAGE_HISTORY_MIN_MICROS {
+        let reported_rate = u128::from(bytes) * u128::from(age);
+        let historical_rate = u128::from(USAGE_SPIKE_FACTOR) * u128::from(node_info.data_served) * u128::from(elapsed_micros);
+        if reported_rate > historical_rate {
+            return Some(format!(
+                "Reported rate is more than {USAGE_SPIKE_FACTOR} times the node's historical rate"
+            ));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt as _;
+    use linera_sdk::{
+        linera_base_types::{
+            AccountOwner, AccountSecretKey, Amount, ApplicationId, BlockHeight, ChainId,
+            ChainOwnership, CryptoHash, Timestamp,
+        },
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+
+    use sha2::{Digest, Sha256};
+
+    use dcdn::{
+        ContentMetadata, DCDNParameters, DCDNResponse, DeliveryReceipt, EconomicChange, Message, ModerationAction,
+        NetworkConfig, NodeAdmission, NodeEndpoint, NodeLocation, NodeProfile, NodeRegistration, Operation, PauseTarget, Pricing, RegionPrice, SignedReceipt, UsageRow, CHALLENGE_CHUNK_SIZE,
+        CHALLENGE_INTERVAL_MICROS, CHALLENGE_RESPONSE_WINDOW_MICROS, DEFAULT_PRICE_PER_BYTE, EPOCH_LENGTH_MICROS,
+        FAILURE_REPORT_INTERVAL_MICROS, INFLATED_USAGE_SLASH_PERCENT, MAX_RELIABILITY_WINDOW_MICROS,
+        MAX_REPORT_REASON_LEN, MIN_NODE_STAKE, PURGE_DEADLINE_MICROS, UNBONDING_PERIOD_MICROS, USAGE_HISTORY_MIN_MICROS,
+        USAGE_WINDOW_MICROS, required_approvals,
+    };
+
+    use super::{DCDNContract, DCDNState, ModerationRecordKind, NodeEventKind, PurgeStatus, ReviewStatus, ShardRequestStatus};
+
+    #[test]
+    fn cache_requests_maintain_node_index() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"cached bytes");
+        register_node(&mut app, "node-1");
+
+        let response = app
+            .execute_operation(Operation::RequestCache {
+                content_id: content_id.clone(),
+                node_id: "node-1".to_string(),
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::CacheRequestAccepted));
+        assert_eq!(node_contents(&app, "node-1"), vec![content_id.clone()]);
+
+        app.execute_operation(Operation::UpdateAvailability {
+            content_id,
+            node_id: "node-1".to_string(),
+            available: false,
+        })
+        .blocking_wait();
+        assert!(node_contents(&app, "node-1").is_empty());
+    }
+
+    #[test]
+    fn reconfirming_availability_keeps_original_claim() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"replicated bytes");
+        register_node(&mut app, "node-1");
+
+        app.execute_operation(Operation::RequestCache {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+        })
+        .blocking_wait();
+        app.runtime.set_system_time(Timestamp::from(5_000));
+        app.execute_operation(Operation::UpdateAvailability {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+            available: true,
+        })
+        .blocking_wait();
+
+        let holders = app.state.content_availability.try_load_entry(&content_id).blocking_wait().unwrap().unwrap();
+        let entry = holders.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(entry.claimed_at, 0);
+        assert_eq!(entry.updated_at, 5_000);
+    }
+
+    #[test]
+    fn download_tracks_access_in_stats() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"popular bytes");
+
+        app.runtime.set_system_time(Timestamp::from(7_000));
+        let response = app
+            .execute_operation(Operation::Download { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::DownloadSuccess { content } if content == b"popular bytes"));
+
+        let stats = app.state.content_stats.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(stats.access_count, 1);
+        assert_eq!(stats.last_accessed, 7_000);
+    }
+
+    #[test]
+    fn register_node_rejects_invalid_location() {
+        let mut app = create_and_instantiate_app();
+        let locations = [
+            NodeLocation { latitude_micros: 91_000_000, ..berlin() },
+            NodeLocation { latitude_micros: i32::MIN, ..berlin() },
+            NodeLocation { longitude_micros: i32::MIN, ..berlin() },
+        ];
+
+        for location in locations {
+            let response = app
+                .execute_operation(Operation::RegisterNode {
+                    node_id: "node-1".to_string(),
+                    location,
+                    capacity: 1_000_000,
+                    bandwidth: 10_000,
+                    profile: edge_profile(),
+                    stake: MIN_NODE_STAKE,
+                })
+                .blocking_wait();
+            assert!(matches!(response, DCDNResponse::Error { .. }));
+        }
+        assert_eq!(*app.state.node_count.get(), 0);
+    }
+
+    #[test]
+    fn usage_window_resets_after_interval() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        register_node(&mut app, "node-1");
+        app.execute_operation(Operation::RequestCache {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+        })
+        .blocking_wait();
+
+        report_usage(&mut app, "node-1", &content_id, 400, 0);
+        app.runtime.set_system_time(Timestamp::from(USAGE_WINDOW_MICROS));
+        app.execute_operation(Operation::Heartbeat { node_id: "node-1".to_string() })
+            .blocking_wait();
+        report_usage(&mut app, "node-1", &content_id, 100, 1);
+
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.last_heartbeat, USAGE_WINDOW_MICROS);
+        assert_eq!(node_info.window_start, USAGE_WINDOW_MICROS);
+        assert_eq!(node_info.window_bytes, 100);
+        assert_eq!(node_info.data_served, 500);
+    }
+
+    #[test]
+    fn invalid_receipts_are_rejected() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        let client = AccountSecretKey::generate();
+
+        // Receipts from a client without an on-chain download are not accepted
+        let valid = signed_receipt(&client, "node-1", &content_id, 100, 3);
+        let response = app
+            .execute_operation(Operation::ReportUsage { node_id: "node-1".to_string(), receipts: vec![valid.clone()] })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        download_as(&mut app, &client, &content_id);
+
+        let mut forged = signed_receipt(&client, "node-1", &content_id, 100, 0);
+        forged.receipt.bytes = 1_000;
+        let oversized = signed_receipt(&client, "node-1", &content_id, 1_001, 1);
+        let other_node = signed_receipt(&client, "node-2", &content_id, 100, 2);
+        let mut other_chain = valid.receipt.clone();
+        other_chain.chain_id = ChainId(CryptoHash::test_hash("other chain"));
+        let other_chain = SignedReceipt::new(other_chain, &client);
+        let batches = [
+            vec![forged],
+            vec![oversized],
+            vec![other_node],
+            vec![other_chain],
+            vec![valid.clone(), valid.clone()],
+            vec![valid.clone(), signed_receipt(&client, "node-1", &content_id, 100, 4)],
+        ];
+        for receipts in batches {
+            let response = app
+                .execute_operation(Operation::ReportUsage { node_id: "node-1".to_string(), receipts })
+                .blocking_wait();
+            assert!(matches!(response, DCDNResponse::Error { .. }));
+        }
+        assert_eq!(*app.state.total_data_served.get(), 0);
+
+        for expected_success in [true, false] {
+            let response = app
+                .execute_operation(Operation::ReportUsage {
+                    node_id: "node-1".to_string(),
+                    receipts: vec![valid.clone()],
+                })
+                .blocking_wait();
+            assert_eq!(matches!(response, DCDNResponse::UsageFlagged { .. }), expected_success);
+        }
+        assert_eq!(app.state.pending_reviews.indices().blocking_wait().unwrap().len(), 1);
+        assert!(app.state.downloads.get(&(AccountOwner::from(client.public()), content_id)).blocking_wait().unwrap().is_none());
+    }
+
+    #[test]
+    fn operators_cannot_sign_their_own_receipts() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        let operator_key = AccountSecretKey::generate();
+        let operator = AccountOwner::from(operator_key.public());
+        app.runtime.set_owner_balance(operator, OPERATOR_BALANCE);
+        app.runtime.set_authenticated_signer(operator);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        download_as(&mut app, &operator_key, &content_id);
+
+        let response = app
+            .execute_operation(Operation::ReportUsage {
+                node_id: "node-1".to_string(),
+                receipts: vec![signed_receipt(&operator_key, "node-1", &content_id, 100, 0)],
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        assert_eq!(*app.state.total_data_served.get(), 0);
+    }
+
+    #[test]
+    fn usage_batches_are_credited_once() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        app.runtime.set_system_time(Timestamp::from(2 * USAGE_WINDOW_MICROS));
+
+        let rows = vec![UsageRow { content_id: content_id.clone(), bytes: 2_500, request_count: 3 }];
+        let batch = |window_start, window_end, rows: &Vec<UsageRow>| Operation::ReportUsageBatch {
+            node_id: "node-1".to_string(),
+            window_start,
+            window_end,
+            rows: rows.clone(),
+        };
+        // A node's first report always waits for an admin's review
+        let response = app.execute_operation(batch(0, USAGE_WINDOW_MICROS, &rows)).blocking_wait();
+        let DCDNResponse::UsageFlagged { report_id } = response else {
+            panic!("Expected the batch to be flagged");
+        };
+        assert_eq!(*app.state.total_data_served.get(), 0);
+
+        // Replaying or overlapping the window is rejected
+        let response = app.execute_operation(batch(0, USAGE_WINDOW_MICROS, &rows)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let response = app
+            .execute_operation(batch(USAGE_WINDOW_MICROS - 1, 2 * USAGE_WINDOW_MICROS, &rows))
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        // More bytes than the requests could have served is rejected
+        let inflated = vec![UsageRow { content_id: content_id.clone(), bytes: 3_001, request_count: 3 }];
+        let response = app
+            .execute_operation(batch(USAGE_WINDOW_MICROS, 2 * USAGE_WINDOW_MICROS, &inflated))
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(Operation::ReviewUsageReport { report_id, approve: true }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::UsageReportReviewed));
+
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 2_500);
+        assert_eq!(node_info.reported_until, USAGE_WINDOW_MICROS);
+        let stats = app.state.content_stats.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!((stats.bytes_served, stats.requests_served), (2_500, 3));
+        assert_eq!(*app.state.total_data_served.get(), 2_500);
+        assert_eq!(node_info.earned, Amount::ZERO);
+
+        // Later plausible batches are credited at once, but never paid from escrow
+        app.runtime.set_authenticated_signer(operator());
+        let response = app
+            .execute_operation(batch(USAGE_WINDOW_MICROS, 2 * USAGE_WINDOW_MICROS, &rows))
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::UsageReported));
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 5_000);
+        assert_eq!(node_info.reported_until, 2 * USAGE_WINDOW_MICROS);
+        assert_eq!(*app.state.total_data_served.get(), 5_000);
+        assert_eq!(node_info.earned, Amount::ZERO);
+    }
+
+    #[test]
+    fn usage_overflowing_totals_is_rejected() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        report_usage(&mut app, "node-1", &content_id, 1_000, 0);
+        app.runtime.set_system_time(Timestamp::from(2 * USAGE_WINDOW_MICROS));
+
+        let rows = vec![UsageRow { content_id: content_id.clone(), bytes: u64::MAX, request_count: u64::MAX }];
+        let response = app
+            .execute_operation(Operation::ReportUsageBatch {
+                node_id: "node-1".to_string(),
+                window_start: 0,
+                window_end: USAGE_WINDOW_MICROS,
+                rows,
+            })
+            .blocking_wait();
+        let DCDNResponse::UsageFlagged { report_id } = response else {
+            panic!("Expected the batch to be flagged");
+        };
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(Operation::ReviewUsageReport { report_id, approve: true }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 1_000);
+        let stats = app.state.content_stats.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!((stats.bytes_served, stats.requests_served), (1_000, 1));
+        assert_eq!(*app.state.total_data_served.get(), 1_000);
+        assert!(app.state.pending_reviews.contains(&report_id).blocking_wait().unwrap());
+    }
+
+    #[test]
+    fn usage_batches_overflowing_their_sum_are_rejected() {
+        let mut app = create_and_instantiate_app();
+        let first_id = upload(&mut app, &[0u8; 1_000]);
+        let second_id = upload(&mut app, &[1u8; 1_000]);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &first_id, "node-1");
+        cache(&mut app, &second_id, "node-1");
+        app.runtime.set_system_time(Timestamp::from(2 * USAGE_WINDOW_MICROS));
+
+        let rows = vec![
+            UsageRow { content_id: first_id, bytes: u64::MAX, request_count: u64::MAX },
+            UsageRow { content_id: second_id, bytes: 1, request_count: 1 },
+        ];
+        let response = app
+            .execute_operation(Operation::ReportUsageBatch {
+                node_id: "node-1".to_string(),
+                window_start: 0,
+                window_end: USAGE_WINDOW_MICROS,
+                rows,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        assert!(app.state.pending_reviews.indices().blocking_wait().unwrap().is_empty());
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.reported_until, 0);
+    }
+
+    #[test]
+    fn usage_beyond_bandwidth_is_flagged_for_review() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        app.runtime.set_system_time(Timestamp::from(2 * USAGE_WINDOW_MICROS));
+
+        // The node declared 10_000 bytes/s, so a minute cannot carry a million bytes
+        let report_batch = |app: &mut DCDNContract, window_start| {
+            app.execute_operation(Operation::ReportUsageBatch {
+                node_id: "node-1".to_string(),
+                window_start,
+                window_end: window_start + USAGE_WINDOW_MICROS,
+                rows: vec![UsageRow { content_id: content_id.clone(), bytes: 1_000_000, request_count: 1_000 }],
+            })
+            .blocking_wait()
+        };
+        let DCDNResponse::UsageFlagged { report_id: approved_id } = report_batch(&mut app, 0) else {
+            panic!("Expected the report to be flagged");
+        };
+        let DCDNResponse::UsageFlagged { report_id: rejected_id } = report_batch(&mut app, USAGE_WINDOW_MICROS) else {
+            panic!("Expected the report to be flagged");
+        };
+        assert_eq!(*app.state.total_data_served.get(), 0);
+        assert_eq!(app.state.pending_reviews.indices().blocking_wait().unwrap(), vec![approved_id, rejected_id]);
+
+        // Only admins may review
+        let review = |report_id, approve| Operation::ReviewUsageReport { report_id, approve };
+        let response = app.execute_operation(review(approved_id, true)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(review(approved_id, true)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::UsageReportReviewed));
+        let response = app.execute_operation(review(rejected_id, false)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::UsageReportReviewed));
+        let response = app.execute_operation(review(rejected_id, true)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let penalty = Amount::from_attos(u128::from(MIN_NODE_STAKE) * INFLATED_USAGE_SLASH_PERCENT / 100);
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 1_000_000);
+        assert_eq!(node_info.stake, MIN_NODE_STAKE.saturating_sub(penalty));
+        let report = app.state.flagged_reports.get(&rejected_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(report.status, ReviewStatus::Rejected);
+        assert!(app.state.pending_reviews.indices().blocking_wait().unwrap().is_empty());
+    }
+
+    #[test]
+    fn first_usage_report_is_reviewed_before_escrow_pays() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_signer(content_owner());
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        app.execute_operation(Operation::DepositEscrow { amount: Amount::from_tokens(1) })
+            .blocking_wait();
+        app.runtime.set_authenticated_signer(operator());
+        // A bandwidth claim alone does not let a new node settle its first report
+        let response = app
+            .execute_operation(Operation::RegisterNode {
+                node_id: "node-1".to_string(),
+                location: berlin(),
+                capacity: 1_000_000,
+                bandwidth: u64::MAX,
+                profile: edge_profile(),
+                stake: MIN_NODE_STAKE,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::NodeRegistered));
+        cache(&mut app, &content_id, "node-1");
+
+        let client = AccountSecretKey::generate();
+        download_as(&mut app, &client, &content_id);
+        let response = app
+            .execute_operation(Operation::ReportUsage {
+                node_id: "node-1".to_string(),
+                receipts: vec![signed_receipt(&client, "node-1", &content_id, 1_000, 0)],
+            })
+            .blocking_wait();
+        let DCDNResponse::UsageFlagged { report_id } = response else {
+            panic!("Expected the first report to be flagged");
+        };
+        let report = app.state.flagged_reports.get(&report_id).blocking_wait().unwrap().unwrap();
+        assert!(report.reason.contains("first usage report"));
+        assert_eq!(app.state.escrow.get(&content_owner()).blocking_wait().unwrap(), Some(Amount::from_tokens(1)));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(Operation::ReviewUsageReport { report_id, approve: true }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::UsageReportReviewed));
+
+        // Once the node has history, ordinary reports are credited directly
+        app.runtime.set_authenticated_signer(operator());
+        download_as(&mut app, &client, &content_id);
+        let response = app
+            .execute_operation(Operation::ReportUsage {
+                node_id: "node-1".to_string(),
+                receipts: vec![signed_receipt(&client, "node-1", &content_id, 500, 1)],
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::UsageReported));
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 1_500);
+        assert_eq!(node_info.earned, DEFAULT_PRICE_PER_BYTE.saturating_mul(1_500));
+    }
+
+    #[test]
+    fn usage_spike_against_history_is_flagged() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        report_usage(&mut app, "node-1", &content_id, 1_000, 0);
+
+        // Within bandwidth, but far above the node's average since registration
+        app.runtime.set_system_time(Timestamp::from(USAGE_HISTORY_MIN_MICROS));
+        let response = app
+            .execute_operation(Operation::ReportUsageBatch {
+                node_id: "node-1".to_string(),
+                window_start: USAGE_HISTORY_MIN_MICROS - USAGE_WINDOW_MICROS,
+                window_end: USAGE_HISTORY_MIN_MICROS,
+                rows: vec![UsageRow { content_id, bytes: 1_000, request_count: 1 }],
+            })
+            .blocking_wait();
+        let DCDNResponse::UsageFlagged { report_id } = response else {
+            panic!("Expected the report to be flagged");
+        };
+        let report = app.state.flagged_reports.get(&report_id).blocking_wait().unwrap().unwrap();
+        assert!(report.reason.contains("historical rate"));
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 1_000);
+    }
+
+    #[test]
+    fn usage_is_paid_from_owner_escrow() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_signer(content_owner());
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        let response = app
+            .execute_operation(Operation::DepositEscrow { amount: Amount::from_attos(1_000_000_000) })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::EscrowUpdated { .. }));
+
+        // Nodes in the operator's region are paid a premium
+        let pricing = Pricing {
+            price_per_byte: DEFAULT_PRICE_PER_BYTE,
+            region_prices: vec![RegionPrice { region: berlin().region, price_per_byte: Amount::from_attos(2_000_000) }],
+        };
+        let change = EconomicChange { pricing: Some(pricing), ..EconomicChange::default() };
+        let response = app.execute_operation(Operation::ProposeChange { change: change.clone() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(Operation::ProposeChange { change }).blocking_wait();
+        let DCDNResponse::ProposalCreated { proposal_id } = response else {
+            panic!("Unexpected proposal response: {response:?}");
+        };
+        app.runtime.set_authenticated_signer(second_admin());
+        let response = app.execute_operation(Operation::ApproveProposal { proposal_id }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::ProposalApproved { executed: true }));
+
+        app.runtime.set_authenticated_signer(operator());
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+        report_usage(&mut app, "node-1", &content_id, 400, 0);
+        // The escrow only covers 100 of these bytes
+        report_usage(&mut app, "node-1", &content_id, 1_000, 1);
+
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.data_served, 1_400);
+        assert_eq!(node_info.earned, Amount::from_attos(1_000_000_000));
+        assert_eq!(app.state.escrow.get(&content_owner()).blocking_wait().unwrap(), Some(Amount::ZERO));
+
+        close_epoch(&mut app, 0);
+        let response = app.execute_operation(Operation::ClaimEarnings).blocking_wait();
+        assert!(matches!(response, DCDNResponse::EarningsClaimed { amount } if amount == Amount::from_attos(1_000_000_000)));
+        assert_eq!(
+            app.runtime.owner_balance(operator()),
+            OPERATOR_BALANCE.saturating_sub(MIN_NODE_STAKE).saturating_add(Amount::from_attos(1_000_000_000))
+        );
+        let response = app.execute_operation(Operation::ClaimEarnings).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+    }
+
+    #[test]
+    fn earnings_are_settled_per_epoch() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_signer(content_owner());
+        let content_id = upload(&mut app, &[0u8; 1_000]);
+        app.execute_operation(Operation::DepositEscrow { amount: Amount::from_tokens(1) })
+            .blocking_wait();
+        app.runtime.set_authenticated_signer(operator());
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+
+        report_usage(&mut app, "node-1", &content_id, 1_000, 0);
+        app.runtime.set_system_time(Timestamp::from(EPOCH_LENGTH_MICROS));
+        report_usage(&mut app, "node-1", &content_id, 3, 1);
+
+        // Earnings stay locked until their epoch is closed, which needs the epoch to be over
+        let response = app.execute_operation(Operation::ClaimEarnings).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let response = app.execute_operation(Operation::CloseEpoch { epoch: 1 }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        close_epoch(&mut app, 0);
+        let response = app.execute_operation(Operation::CloseEpoch { epoch: 0 }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        // Epochs without usage have nothing to close
+        app.runtime.set_system_time(Timestamp::from(3 * EPOCH_LENGTH_MICROS));
+        let response = app.execute_operation(Operation::CloseEpoch { epoch: 2 }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        assert!(app.state.epochs.get(&2).blocking_wait().unwrap().is_none());
+
+        let first_epoch_pay = DEFAULT_PRICE_PER_BYTE.saturating_mul(1_000);
+        let response = app.execute_operation(Operation::ClaimEarnings).blocking_wait();
+        assert!(matches!(response, DCDNResponse::EarningsClaimed { amount } if amount == first_epoch_pay));
+
+        let node_statements = app.state.node_statements.try_load_entry("node-1").blocking_wait().unwrap().unwrap();
+        let statements = node_statements.index_values().blocking_wait().unwrap();
+        let served = statements.iter().map(|(epoch, statement)| (*epoch, statement.bytes_served)).collect::<Vec<_>>();
+        assert_eq!(served, vec![(0, 1_000), (1, 3)]);
+        let owner_statements = app.state.owner_statements.try_load_entry(&content_owner()).blocking_wait().unwrap().unwrap();
+        let statement = owner_statements.get(&1).blocking_wait().unwrap().unwrap();
+        assert_eq!(statement.spent, DEFAULT_PRICE_PER_BYTE.saturating_mul(3));
+        let summary = app.state.epochs.get(&1).blocking_wait().unwrap().unwrap();
+        assert!(!summary.closed);
+    }
+
+    #[test]
+    fn failures_and_flaps_are_recorded_in_node_history() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"flaky bytes");
+        register_node(&mut app, "node-1");
+
+        app.execute_operation(Operation::RequestCache {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+        })
+        .blocking_wait();
+        app.execute_operation(Operation::UpdateAvailability {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+            available: false,
+        })
+        .blocking_wait();
+        let response = app
+            .execute_operation(Operation::ReportNodeFailure {
+                node_id: "node-1".to_string(),
+                content_id,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::FailureReported));
+
+        assert_eq!(
+            node_event_kinds(&app, "node-1"),
+            vec![NodeEventKind::Heartbeat, NodeEventKind::AvailabilityFlap, NodeEventKind::ClientFailure]
+        );
+    }
+
+    #[test]
+    fn failure_reports_are_limited_and_history_is_trimmed() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"flaky bytes");
+        register_node(&mut app, "node-1");
+        let report = || Operation::ReportNodeFailure { node_id: "node-1".to_string(), content_id: content_id.clone() };
+
+        // Reports must be signed, and each client counts once per interval
+        app.runtime.set_authenticated_signer(None);
+        assert!(matches!(app.execute_operation(report()).blocking_wait(), DCDNResponse::Error { .. }));
+        app.runtime.set_authenticated_signer(content_owner());
+        assert!(matches!(app.execute_operation(report()).blocking_wait(), DCDNResponse::FailureReported));
+        assert!(matches!(app.execute_operation(report()).blocking_wait(), DCDNResponse::Error { .. }));
+        app.runtime.set_authenticated_signer(chain_owner());
+        assert!(matches!(app.execute_operation(report()).blocking_wait(), DCDNResponse::FailureReported));
+        app.runtime.set_system_time(Timestamp::from(FAILURE_REPORT_INTERVAL_MICROS));
+        app.runtime.set_authenticated_signer(content_owner());
+        assert!(matches!(app.execute_operation(report()).blocking_wait(), DCDNResponse::FailureReported));
+        assert_eq!(node_event_kinds(&app, "node-1").len(), 4);
+
+        // Events no reliability window reaches are dropped as new ones arrive
+        app.runtime.set_system_time(Timestamp::from(MAX_RELIABILITY_WINDOW_MICROS + 1));
+        app.runtime.set_authenticated_signer(operator());
+        app.execute_operation(Operation::Heartbeat { node_id: "node-1".to_string() }).blocking_wait();
+        assert_eq!(
+            node_event_kinds(&app, "node-1"),
+            vec![NodeEventKind::ClientFailure, NodeEventKind::Heartbeat]
+        );
+    }
+
+    #[test]
+    fn update_node_validates_profile() {
+        let mut app = create_and_instantiate_app();
+        register_node(&mut app, "node-1");
+
+        let mut profile = edge_profile();
+        profile.endpoints[0].host = "bad host".to_string();
+        let response = app
+            .execute_operation(Operation::UpdateNode { node_id: "node-1".to_string(), profile })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let mut profile = edge_profile();
+        profile.protocols.push("h3".to_string());
+        let response = app
+            .execute_operation(Operation::UpdateNode { node_id: "node-1".to_string(), profile: profile.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::NodeUpdated));
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.profile, profile);
+    }
+
+    #[test]
+    fn register_node_locks_stake() {
+        let mut app = create_and_instantiate_app();
+
+        let response = app
+            .execute_operation(Operation::RegisterNode {
+                node_id: "node-1".to_string(),
+                location: berlin(),
+                capacity: 1_000_000,
+                bandwidth: 10_000,
+                profile: edge_profile(),
+                stake: Amount::from_tokens(1),
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        register_node(&mut app, "node-1");
+        assert_eq!(app.runtime.owner_balance(operator()), OPERATOR_BALANCE.saturating_sub(MIN_NODE_STAKE));
+        assert_eq!(app.runtime.owner_balance(application_owner()), MIN_NODE_STAKE);
+    }
+
+    #[test]
+    fn stake_is_returned_after_unbonding() {
+        let mut app = create_and_instantiate_app();
+        register_node(&mut app, "node-1");
+
+        let response = app
+            .execute_operation(Operation::DeregisterNode { node_id: "node-1".to_string() })
+            .blocking_wait();
+        assert!(matches!(
+            response,
+            DCDNResponse::NodeDeregistered { withdrawable_at } if withdrawable_at == UNBONDING_PERIOD_MICROS
+        ));
+        assert_eq!(*app.state.node_count.get(), 0);
+
+        let response = app
+            .execute_operation(Operation::WithdrawStake { node_id: "node-1".to_string() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_system_time(Timestamp::from(UNBONDING_PERIOD_MICROS));
+        let response = app
+            .execute_operation(Operation::WithdrawStake { node_id: "node-1".to_string() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::StakeWithdrawn { amount } if amount == MIN_NODE_STAKE));
+        assert_eq!(app.runtime.owner_balance(operator()), OPERATOR_BALANCE);
+        assert!(app.state.nodes.get("node-1").blocking_wait().unwrap().is_none());
+    }
+
+    #[test]
+    fn usage_for_content_not_held_is_slashed() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"unclaimed bytes");
+        register_node(&mut app, "node-1");
+        let client = AccountSecretKey::generate();
+        download_as(&mut app, &client, &content_id);
+
+        let response = app
+            .execute_operation(Operation::ReportUsage {
+                node_id: "node-1".to_string(),
+                receipts: vec![signed_receipt(&client, "node-1", &content_id, 10, 0)],
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let penalty = Amount::from_attos(u128::from(MIN_NODE_STAKE) * INFLATED_USAGE_SLASH_PERCENT / 100);
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.stake, MIN_NODE_STAKE.saturating_sub(penalty));
+        assert_eq!(node_info.data_served, 0);
+        assert_eq!(*app.state.slashed_stake.get(), penalty);
+    }
+
+    #[test]
+    fn storage_challenge_passes_with_correct_proof() {
+        let mut app = create_and_instantiate_app();
+        let content = vec![7u8; 3 * CHALLENGE_CHUNK_SIZE as usize];
+        let content_id = upload(&mut app, &content);
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+
+        let (challenge_id, chunk_index, nonce) = issue_challenge(&mut app, &content_id, "node-1");
+        let start = (chunk_index * CHALLENGE_CHUNK_SIZE) as usize;
+        let chunk = &content[start..start + CHALLENGE_CHUNK_SIZE as usize];
+        let mut hasher = Sha256::new();
+        hasher.update(hex::decode(nonce).unwrap());
+        hasher.update(chunk);
+
+        let response = app
+            .execute_operation(Operation::RespondChallenge {
+                challenge_id,
+                chunk_hash: format!("{:x}", Sha256::digest(chunk)),
+                proof: format!("{:x}", hasher.finalize()),
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::ChallengePassed));
+        assert_eq!(node_contents(&app, "node-1"), vec![content_id]);
+    }
+
+    #[test]
+    fn expired_challenge_removes_node_and_slashes() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"audited bytes");
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+
+        let (challenge_id, _, _) = issue_challenge(&mut app, &content_id, "node-1");
+        let response = app
+            .execute_operation(Operation::ExpireChallenge { challenge_id })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_system_time(Timestamp::from(CHALLENGE_RESPONSE_WINDOW_MICROS + 1));
+        let response = app
+            .execute_operation(Operation::ExpireChallenge { challenge_id })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::ChallengeFailed));
+
+        assert!(node_contents(&app, "node-1").is_empty());
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert!(node_info.stake < MIN_NODE_STAKE);
+    }
+
+    #[test]
+    fn challenges_are_restricted_and_rate_limited() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_signer(content_owner());
+        let content_id = upload(&mut app, b"owned bytes");
+        app.runtime.set_authenticated_signer(operator());
+        register_node(&mut app, "node-1");
+        let challenge = || Operation::IssueChallenge { content_id: content_id.clone(), node_id: "node-1".to_string() };
+
+        // A third party listing content on the node does not make it challengeable
+        app.runtime.set_authenticated_signer(content_owner());
+        cache(&mut app, &content_id, "node-1");
+        let response = app.execute_operation(challenge()).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(operator());
+        cache(&mut app, &content_id, "node-1");
+        let response = app.execute_operation(challenge()).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(content_owner());
+        let (challenge_id, _, _) = issue_challenge(&mut app, &content_id, "node-1");
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(challenge()).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_system_time(Timestamp::from(CHALLENGE_RESPONSE_WINDOW_MICROS + 1));
+        let response = app.execute_operation(Operation::ExpireChallenge { challenge_id }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::ChallengeFailed));
+        assert!(app.state.challenges.get(&challenge_id).blocking_wait().unwrap().is_none());
+
+        app.runtime.set_authenticated_signer(operator());
+        cache(&mut app, &content_id, "node-1");
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(challenge()).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        app.runtime.set_system_time(Timestamp::from(CHALLENGE_INTERVAL_MICROS));
+        issue_challenge(&mut app, &content_id, "node-1");
+    }
+
+    #[test]
+    fn withdrawn_copies_cancel_pending_challenges() {
+        let mut app = create_and_instantiate_app();
+        let deleted_id = upload(&mut app, b"deleted bytes");
+        let taken_down_id = upload(&mut app, b"taken down bytes");
+        register_node(&mut app, "node-1");
+        register_node(&mut app, "node-2");
+        cache(&mut app, &deleted_id, "node-1");
+        cache(&mut app, &taken_down_id, "node-2");
+        let (deleted_challenge, _, _) = issue_challenge(&mut app, &deleted_id, "node-1");
+        let (taken_down_challenge, _, _) = issue_challenge(&mut app, &taken_down_id, "node-2");
+
+        let response = app
+            .execute_operation(Operation::DeleteContent { content_id: deleted_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::ContentDeleted));
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app
+            .execute_operation(Operation::Moderate {
+                content_id: taken_down_id,
+                action: ModerationAction::TakeDown,
+                reason: "Infringing".to_string(),
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::ModerationUpdated));
+
+        // Neither node can be failed for content it was told to drop
+        app.runtime.set_system_time(Timestamp::from(CHALLENGE_RESPONSE_WINDOW_MICROS + 1));
+        for challenge_id in [deleted_challenge, taken_down_challenge] {
+            let response = app.execute_operation(Operation::ExpireChallenge { challenge_id }).blocking_wait();
+            assert!(matches!(response, DCDNResponse::Error { .. }));
+        }
+        for node_id in ["node-1", "node-2"] {
+            let pending = app.state.pending_challenges.try_load_entry(node_id).blocking_wait().unwrap().unwrap();
+            assert!(pending.indices().blocking_wait().unwrap().is_empty());
+            let node_info = app.state.nodes.get(node_id).blocking_wait().unwrap().unwrap();
+            assert_eq!(node_info.stake, MIN_NODE_STAKE);
+        }
+    }
+
+    #[test]
+    fn only_owner_can_delete_content() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"short-lived bytes");
+        register_node(&mut app, "node-1");
+        app.execute_operation(Operation::RequestCache {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+        })
+        .blocking_wait();
+
+        app.runtime.set_authenticated_signer(content_owner());
+        let response = app
+            .execute_operation(Operation::DeleteContent { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(operator());
+        let response = app
+            .execute_operation(Operation::DeleteContent { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::ContentDeleted));
+        assert!(app.state.content_map.get(&content_id).blocking_wait().unwrap().is_none());
+        assert!(node_contents(&app, "node-1").is_empty());
+    }
+
+    #[test]
+    fn purge_drops_nodes_on_ack_or_deadline() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"stale bytes");
+        register_node(&mut app, "node-1");
+        register_node(&mut app, "node-2");
+        cache(&mut app, &content_id, "node-1");
+        cache(&mut app, &content_id, "node-2");
+
+        app.runtime.set_authenticated_signer(content_owner());
+        let response = app
+            .execute_operation(Operation::Purge { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(operator());
+        let response = app
+            .execute_operation(Operation::Purge { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::PurgeRequested { deadline } if deadline == PURGE_DEADLINE_MICROS));
+
+        let response = app
+            .execute_operation(Operation::AcknowledgePurge { content_id: content_id.clone(), node_id: "node-1".to_string() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::PurgeAcknowledged));
+        assert!(node_contents(&app, "node-1").is_empty());
+        assert_eq!(node_contents(&app, "node-2"), vec![content_id.clone()]);
+
+        let response = app
+            .execute_operation(Operation::ExpirePurge { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_system_time(Timestamp::from(PURGE_DEADLINE_MICROS));
+        let response = app
+            .execute_operation(Operation::ExpirePurge { content_id: content_id.clone() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::PurgeExpired { node_ids } if node_ids == ["node-2"]));
+        assert!(node_contents(&app, "node-2").is_empty());
+
+        let purge = app.state.purges.get(&content_id).blocking_wait().unwrap().unwrap();
+        let statuses = purge.targets.iter().map(|target| target.status).collect::<Vec<_>>();
+        assert_eq!(statuses, [PurgeStatus::Acknowledged, PurgeStatus::Expired]);
+    }
+
+    #[test]
+    fn bounced_replication_is_rolled_back() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"replicated bytes");
+        let target_chain = ChainId(CryptoHash::test_hash("target chain"));
+
+        let response = app
+            .execute_operation(Operation::Replicate { content_id: content_id.clone(), target_chain })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::ReplicationSent));
+        let response = app
+            .execute_operation(Operation::Replicate { content_id: content_id.clone(), target_chain })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_message_origin_chain_id(target_chain);
+        app.runtime.set_message_is_bouncing(Some(true));
+        app.execute_message(Message::ReplicateContent {
+            content_id: content_id.clone(),
+            content: b"replicated bytes".to_vec(),
+            metadata: metadata(b"replicated bytes"),
+            owner: Some(operator()),
+        })
+        .blocking_wait();
+
+        let replications = app.state.replications.try_load_entry(&content_id).blocking_wait().unwrap().unwrap();
+        assert!(replications.get(&target_chain).blocking_wait().unwrap().is_none());
+    }
+
+    #[test]
+    fn remote_copies_are_kept_apart_from_local_nodes() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"widely cached bytes");
+        register_node(&mut app, "node-1");
+        let first_chain = ChainId(CryptoHash::test_hash("first node chain"));
+        let second_chain = ChainId(CryptoHash::test_hash("second node chain"));
+        for node_chain in [first_chain, second_chain] {
+            let response = app
+                .execute_operation(Operation::RequestRemoteCache {
+                    content_id: content_id.clone(),
+                    node_id: "node-1".to_string(),
+                    node_chain,
+                })
+                .blocking_wait();
+            assert!(matches!(response, DCDNResponse::CacheRequestSent));
+        }
+
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.runtime.set_message_origin_chain_id(first_chain);
+        app.execute_message(Message::CacheConfirmed { content_id: content_id.clone(), node_id: "node-1".to_string() })
+            .blocking_wait();
+        // The second chain never confirmed, so its updates are ignored
+        app.runtime.set_message_origin_chain_id(second_chain);
+        app.execute_message(Message::AvailabilityUpdate {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+            available: true,
+        })
+        .blocking_wait();
+
+        assert!(node_contents(&app, "node-1").is_empty());
+        assert!(app.state.content_availability.try_load_entry(&content_id).blocking_wait().unwrap().is_none());
+        let remote_copy = |app: &DCDNContract, node_chain: ChainId| {
+            let requests = app.state.remote_caches.try_load_entry(&content_id).blocking_wait().unwrap().unwrap();
+            requests.get(&(node_chain, "node-1".to_string())).blocking_wait().unwrap().unwrap().copy
+        };
+        assert!(remote_copy(&app, first_chain).is_some());
+        assert!(remote_copy(&app, second_chain).is_none());
+
+        app.runtime.set_message_origin_chain_id(first_chain);
+        app.execute_message(Message::AvailabilityUpdate {
+            content_id: content_id.clone(),
+            node_id: "node-1".to_string(),
+            available: false,
+        })
+        .blocking_wait();
+        assert!(remote_copy(&app, first_chain).is_none());
+    }
+
+    #[test]
+    fn node_capacity_is_positive_and_totals_saturate() {
+        let mut app = create_and_instantiate_app();
+        let register = |node_id: &str, capacity| Operation::RegisterNode {
+            node_id: node_id.to_string(),
+            location: berlin(),
+            capacity,
+            bandwidth: 10_000,
+            profile: edge_profile(),
+            stake: MIN_NODE_STAKE,
+        };
+        let response = app.execute_operation(register("node-0", 0)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let response = app.execute_operation(register("node-1", u64::MAX)).blocking_wait();
+        assert!(matches!(response, DCDNResponse::NodeRegistered));
+        register_node(&mut app, "node-2");
+        assert_eq!(*app.state.total_capacity.get(), u64::MAX);
+
+        let response = app
+            .execute_operation(Operation::UpdateCapacity { node_id: "node-2".to_string(), capacity: u64::MAX })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::NodeUpdated));
+        assert_eq!(*app.state.total_capacity.get(), u64::MAX);
+    }
+
+    #[test]
+    fn registry_refuses_taken_node_ids_and_mirrored_nodes_stay_read_only() {
+        let mut app = create_and_instantiate_app();
+        let first_chain = ChainId(CryptoHash::test_hash("first node chain"));
+        let second_chain = ChainId(CryptoHash::test_hash("second node chain"));
+        let registration = NodeRegistration {
+            node_id: "node-1".to_string(),
+            operator: operator(),
+            location: berlin(),
+            capacity: 1_000_000,
+            bandwidth: 10_000,
+            profile: edge_profile(),
+            stake: MIN_NODE_STAKE,
+        };
+
+        app.runtime.set_message_is_bouncing(Some(false));
+        for origin in [first_chain, second_chain] {
+            app.runtime.set_message_origin_chain_id(origin);
+            app.execute_message(Message::NodeRegistered { registration: registration.clone() })
+                .blocking_wait();
+        }
+        let node_info = app.state.nodes.get("node-1").blocking_wait().unwrap().unwrap();
+        assert_eq!(node_info.home_chain, first_chain);
+        assert!(app.runtime.created_send_message_requests().iter().any(|request| {
+            request.destination == second_chain
+                && matches!(&request.message, Message::NodeRejected { node_id } if node_id == "node-1")
+        }));
+
+        // The registry's copy holds no stake and cannot be operated from here
+        let response = app.execute_operation(Operation::Heartbeat { node_id: "node-1".to_string() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let response = app.execute_operation(Operation::DeregisterNode { node_id: "node-1".to_string() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        app.runtime.set_message_origin_chain_id(first_chain);
+        app.execute_message(Message::NodeDeregistered { node_id: "node-1".to_string() }).blocking_wait();
+        app.runtime.set_system_time(Timestamp::from(UNBONDING_PERIOD_MICROS));
+        let response = app.execute_operation(Operation::WithdrawStake { node_id: "node-1".to_string() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        assert_eq!(app.runtime.owner_balance(operator()), OPERATOR_BALANCE);
+    }
+
+    #[test]
+    fn rejected_registration_can_be_refunded() {
+        let mut app = create_and_instantiate_app();
+        let registry_chain = ChainId(CryptoHash::test_hash("registry chain"));
+        app.runtime.set_application_parameters(DCDNParameters {
+            registry_chain: Some(registry_chain),
+            config: NetworkConfig { admins: vec![chain_owner(), second_admin()], ..NetworkConfig::default() },
+            ..DCDNParameters::default()
+        });
+        register_node(&mut app, "node-1");
+        assert_eq!(app.runtime.owner_balance(operator()), OPERATOR_BALANCE.saturating_sub(MIN_NODE_STAKE));
+
+        // Only the registry can refuse the node
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.runtime.set_message_origin_chain_id(ChainId(CryptoHash::test_hash("other chain")));
+        app.execute_message(Message::NodeRejected { node_id: "node-1".to_string() }).blocking_wait();
+        let response = app.execute_operation(Operation::WithdrawStake { node_id: "node-1".to_string() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_message_origin_chain_id(registry_chain);
+        app.execute_message(Message::NodeRejected { node_id: "node-1".to_string() }).blocking_wait();
+        let response = app.execute_operation(Operation::WithdrawStake { node_id: "node-1".to_string() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::StakeWithdrawn { amount } if amount == MIN_NODE_STAKE));
+        assert_eq!(app.runtime.owner_balance(operator()), OPERATOR_BALANCE);
+    }
+
+    #[test]
+    fn expired_remote_purges_are_cleared_on_the_node_chain() {
+        let mut app = create_and_instantiate_app();
+        let node_chain = ChainId(CryptoHash::test_hash("node chain"));
+        let content_id = upload(&mut app, b"stale bytes");
+        let response = app
+            .execute_operation(Operation::RequestRemoteCache {
+                content_id: content_id.clone(),
+                node_id: "node-9".to_string(),
+                node_chain,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::CacheRequestSent));
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.runtime.set_message_origin_chain_id(node_chain);
+        app.execute_message(Message::CacheConfirmed { content_id: content_id.clone(), node_id: "node-9".to_string() })
+            .blocking_wait();
+        let response = app.execute_operation(Operation::Purge { content_id: content_id.clone() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::PurgeRequested { .. }));
+
+        // The owner's chain tells the node's chain when the node missed the deadline
+        app.runtime.set_system_time(Timestamp::from(PURGE_DEADLINE_MICROS));
+        let response = app.execute_operation(Operation::ExpirePurge { content_id: content_id.clone() }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::PurgeExpired { node_ids } if node_ids == ["node-9"]));
+        assert!(app.runtime.created_send_message_requests().iter().any(|request| {
+            request.destination == node_chain
+                && matches!(&request.message, Message::PurgeExpired { node_id, .. } if node_id == "node-9")
+        }));
+
+        // On a node's chain, the request and the copy are forgotten
+        let origin_chain = ChainId(CryptoHash::test_hash("origin chain"));
+        let remote_id = format!("{:x}", Sha256::digest(b"remote bytes"));
+        register_node(&mut app, "node-1");
+        app.runtime.set_message_origin_chain_id(origin_chain);
+        app.execute_message(Message::CacheRequest { content_id: remote_id.clone(), node_id: "node-1".to_string(), size: 12 })
+            .blocking_wait();
+        let response = app
+            .execute_operation(Operation::RespondCacheRequest { request_id: 0, accept: true })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::CacheRequestAnswered));
+        let purge = |app: &mut DCDNContract, message| app.execute_message(message).blocking_wait();
+        purge(&mut app, Message::PurgeRequest { content_id: remote_id.clone(), node_id: "node-1".to_string() });
+        let origin_key = (remote_id.clone(), "node-1".to_string());
+        assert!(app.state.pending_purges.get(&origin_key).blocking_wait().unwrap().is_some());
+
+        purge(&mut app, Message::PurgeExpired { content_id: remote_id.clone(), node_id: "node-1".to_string() });
+        assert!(app.state.pending_purges.get(&origin_key).blocking_wait().unwrap().is_none());
+        assert!(app.state.cache_origins.get(&origin_key).blocking_wait().unwrap().is_none());
+        assert!(node_contents(&app, "node-1").is_empty());
+    }
+
+    #[test]
+    fn cancelled_cache_requests_are_forgotten_on_the_node_chain() {
+        let mut app = create_and_instantiate_app();
+        let origin_chain = ChainId(CryptoHash::test_hash("origin chain"));
+        let remote_id = format!("{:x}", Sha256::digest(b"remote bytes"));
+        register_node(&mut app, "node-1");
+        register_node(&mut app, "node-2");
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.runtime.set_message_origin_chain_id(origin_chain);
+        for node_id in ["node-1", "node-2"] {
+            app.execute_message(Message::CacheRequest { content_id: remote_id.clone(), node_id: node_id.to_string(), size: 12 })
+                .blocking_wait();
+        }
+        let response = app
+            .execute_operation(Operation::RespondCacheRequest { request_id: 0, accept: true })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::CacheRequestAnswered));
+
+        // Only the chain that sent the request may cancel it
+        let cancel = |node_id: &str| Message::CancelCacheRequest { content_id: remote_id.clone(), node_id: node_id.to_string() };
+        app.runtime.set_message_origin_chain_id(ChainId(CryptoHash::test_hash("other chain")));
+        app.execute_message(cancel("node-2")).blocking_wait();
+        assert!(app.state.cache_requests.get(&1).blocking_wait().unwrap().is_some());
+
+        app.runtime.set_message_origin_chain_id(origin_chain);
+        app.execute_message(cancel("node-2")).blocking_wait();
+        assert!(app.state.cache_requests.get(&1).blocking_wait().unwrap().is_none());
+        let response = app
+            .execute_operation(Operation::RespondCacheRequest { request_id: 1, accept: true })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        // A request the operator already accepted is purged instead
+        app.execute_message(cancel("node-1")).blocking_wait();
+        let origin_key = (remote_id.clone(), "node-1".to_string());
+        assert_eq!(app.state.pending_purges.get(&origin_key).blocking_wait().unwrap(), Some(origin_chain));
+    }
+
+    #[test]
+    fn replica_with_wrong_hash_is_refused() {
+        let mut app = create_and_instantiate_app();
+        let content_id = format!("{:x}", Sha256::digest(b"original bytes"));
+
+        app.runtime.set_message_origin_chain_id(ChainId(CryptoHash::test_hash("origin chain")));
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.execute_message(Message::ReplicateContent {
+            content_id: content_id.clone(),
+            content: b"tampered bytes".to_vec(),
+            metadata: metadata(b"original bytes"),
+            owner: Some(operator()),
+        })
+        .blocking_wait();
+
+        assert!(app.state.content_map.get(&content_id).blocking_wait().unwrap().is_none());
+    }
+
+    #[test]
+    fn content_operations_are_routed_to_owning_shard() {
+        let mut app = create_and_instantiate_app();
+        let this_chain = ChainId(CryptoHash::test_hash("chain"));
+        let other_shard = ChainId(CryptoHash::test_hash("other shard"));
+        app.runtime.set_application_parameters(DCDNParameters {
+            shard_chains: vec![this_chain, other_shard],
+            ..DCDNParameters::default()
+        });
+        let parameters = app.runtime.application_parameters();
+
+        // Find one content ID for each shard
+        let contents = (0u8..).map(|byte| vec![byte]);
+        let mut local = None;
+        let mut remote = None;
+        for content in contents {
+            let content_id = format!("{:x}", Sha256::digest(&content));
+            match parameters.shard_for(&content_id) {
+                Some(shard) if shard == this_chain => local = local.or(Some(content)),
+                _ => remote = remote.or(Some(content)),
+            }
+            if local.is_some() && remote.is_some() {
+                break;
+            }
+        }
+
+        upload(&mut app, &local.unwrap());
+        let remote = remote.unwrap();
+        let response = app
+            .execute_operation(Operation::Upload { content: remote.clone(), metadata: metadata(&remote) })
+            .blocking_wait();
+        assert!(matches!(
+            response,
+            DCDNResponse::RoutedToShard { shard_chain, request_id: Some(0) } if shard_chain == other_shard
+        ));
+        let remote_id = format!("{:x}", Sha256::digest(&remote));
+        assert!(app.state.content_map.get(&remote_id).blocking_wait().unwrap().is_none());
+
+        // Only the shard the operation went to can report its outcome
+        app.runtime.set_message_is_bouncing(Some(false));
+        for origin in [this_chain, other_shard] {
+            app.runtime.set_message_origin_chain_id(origin);
+            app.execute_message(Message::ShardOperationResult { request_id: 0, error: Some("Rejected".to_string()) })
+                .blocking_wait();
+        }
+        let request = app.state.shard_requests.get(&0).blocking_wait().unwrap().unwrap();
+        assert_eq!(request.status, ShardRequestStatus::Failed);
+        assert_eq!(request.error.as_deref(), Some("Rejected"));
+    }
+
+    #[test]
+    fn shard_operation_outcomes_are_sent_back() {
+        let mut app = create_and_instantiate_app();
+        let client_chain = ChainId(CryptoHash::test_hash("client chain"));
+
+        app.runtime.set_message_origin_chain_id(client_chain);
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.execute_message(Message::ShardOperation {
+            request_id: 3,
+            operation: Operation::DeleteContent { content_id: "missing".to_string() },
+        })
+        .blocking_wait();
+        app.execute_message(Message::ShardOperation {
+            request_id: 4,
+            operation: Operation::Upload { content: b"sharded".to_vec(), metadata: metadata(b"sharded") },
+        })
+        .blocking_wait();
+
+        let requests = app.runtime.created_send_message_requests();
+        assert!(requests.iter().any(|request| {
+            request.destination == client_chain
+                && matches!(&request.message, Message::ShardOperationResult { request_id: 3, error: Some(_) })
+        }));
+        assert!(requests.iter().any(|request| {
+            request.destination == client_chain
+                && matches!(&request.message, Message::ShardOperationResult { request_id: 4, error: None })
+        }));
+    }
+
+    #[test]
+    fn replicas_are_dropped_when_content_changes() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"replicated bytes");
+        let target_chain = ChainId(CryptoHash::test_hash("target chain"));
+        app.execute_operation(Operation::Replicate { content_id: content_id.clone(), target_chain })
+            .blocking_wait();
+        let update = || Operation::UpdateMetadata { content_id: content_id.clone(), metadata: metadata(b"replicated bytes") };
+
+        // Someone else's update is refused and leaves the replicas alone
+        app.runtime.set_authenticated_signer(content_owner());
+        assert!(matches!(app.execute_operation(update()).blocking_wait(), DCDNResponse::Error { .. }));
+        assert!(!app.runtime.created_send_message_requests().iter().any(|request| {
+            matches!(&request.message, Message::DropReplica { .. })
+        }));
+        {
+            let replications = app.state.replications.try_load_entry(&content_id).blocking_wait().unwrap().unwrap();
+            assert!(replications.get(&target_chain).blocking_wait().unwrap().is_some());
+        }
+
+        app.runtime.set_authenticated_signer(operator());
+        let response = app.execute_operation(update()).blocking_wait();
+        assert!(matches!(response, DCDNResponse::MetadataUpdated));
+        assert!(app.runtime.created_send_message_requests().iter().any(|request| {
+            request.destination == target_chain
+                && matches!(&request.message, Message::DropReplica { content_id: dropped } if *dropped == content_id)
+        }));
+        assert!(app.state.replications.try_load_entry(&content_id).blocking_wait().unwrap().is_none());
+
+        // On the receiving side only the chain the replica came from can withdraw it
+        let mut replica_app = create_and_instantiate_app();
+        let source_chain = ChainId(CryptoHash::test_hash("source chain"));
+        replica_app.runtime.set_message_origin_chain_id(source_chain);
+        replica_app.runtime.set_message_is_bouncing(Some(false));
+        replica_app
+            .execute_message(Message::ReplicateContent {
+                content_id: content_id.clone(),
+                content: b"replicated bytes".to_vec(),
+                metadata: metadata(b"replicated bytes"),
+                owner: Some(operator()),
+            })
+            .blocking_wait();
+        assert!(replica_app.state.content_map.get(&content_id).blocking_wait().unwrap().is_some());
+
+        replica_app.runtime.set_message_origin_chain_id(target_chain);
+        replica_app.execute_message(Message::DropReplica { content_id: content_id.clone() }).blocking_wait();
+        assert!(replica_app.state.content_map.get(&content_id).blocking_wait().unwrap().is_some());
+
+        replica_app.runtime.set_message_origin_chain_id(source_chain);
+        replica_app.execute_message(Message::DropReplica { content_id: content_id.clone() }).blocking_wait();
+        assert!(replica_app.state.content_map.get(&content_id).blocking_wait().unwrap().is_none());
+        assert!(replica_app.state.content_bytes.get(&content_id).blocking_wait().unwrap().is_none());
+    }
+
+    #[test]
+    fn metadata_can_only_be_changed_by_its_owner() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"owned bytes");
+        let uploaded = app.state.content_map.get(&content_id).blocking_wait().unwrap().unwrap();
+        let update = || Operation::UpdateMetadata {
+            content_id: content_id.clone(),
+            metadata: ContentMetadata {
+                name: "renamed.bin".to_string(),
+                owner: "mallory".to_string(),
+                created_at: 7,
+                expires_at: Some(0),
+                ..metadata(b"owned bytes")
+            },
+        };
+
+        app.runtime.set_authenticated_signer(content_owner());
+        assert!(matches!(app.execute_operation(update()).blocking_wait(), DCDNResponse::Error { .. }));
+        let content_data = app.state.content_map.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(content_data.metadata.expires_at, None);
+        assert_eq!(content_data.metadata.name, uploaded.metadata.name);
+
+        app.runtime.set_authenticated_signer(operator());
+        assert!(matches!(app.execute_operation(update()).blocking_wait(), DCDNResponse::MetadataUpdated));
+        let content_data = app.state.content_map.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(content_data.metadata.name, "renamed.bin");
+        assert_eq!(content_data.metadata.expires_at, Some(0));
+        assert_eq!(content_data.metadata.owner, uploaded.metadata.owner);
+        assert_eq!(content_data.metadata.created_at, uploaded.metadata.created_at);
+    }
+
+    #[test]
+    fn network_config_is_applied() {
+        let config = NetworkConfig {
+            max_upload_size: 16,
+            default_ttl_micros: Some(1_000),
+            min_node_stake: MIN_NODE_STAKE.saturating_add(Amount::ONE),
+            admins: vec![chain_owner()],
+            node_admission: NodeAdmission::Permissioned,
+            ..NetworkConfig::default()
+        };
+        let mut app = create_and_instantiate_app_with(config.clone());
+        assert_eq!(app.state.config.get().as_ref(), Some(&config));
+
+        let response = app
+            .execute_operation(Operation::Upload { content: vec![0; 17], metadata: metadata(&[0; 17]) })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let content_id = upload(&mut app, b"small bytes");
+        let content_data = app.state.content_map.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(content_data.metadata.expires_at, Some(1_000));
+        let download = || Operation::Download { content_id: content_id.clone() };
+        assert!(matches!(app.execute_operation(download()).blocking_wait(), DCDNResponse::DownloadSuccess { .. }));
+        app.runtime.set_system_time(Timestamp::from(1_000));
+        assert!(matches!(app.execute_operation(download()).blocking_wait(), DCDNResponse::Error { .. }));
+
+        // Uploading the same bytes again replaces the expired copy
+        assert_eq!(upload(&mut app, b"small bytes"), content_id);
+        let content_data = app.state.content_map.get(&content_id).blocking_wait().unwrap().unwrap();
+        assert_eq!(content_data.metadata.expires_at, Some(2_000));
+        assert!(matches!(app.execute_operation(download()).blocking_wait(), DCDNResponse::DownloadSuccess { .. }));
+
+        let response = app
+            .execute_operation(Operation::RegisterNode {
+                node_id: "node-1".to_string(),
+                location: berlin(),
+                capacity: 1_000_000,
+                bandwidth: 10_000,
+                profile: edge_profile(),
+                stake: config.min_node_stake,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        assert_eq!(*app.state.node_count.get(), 0);
+    }
+
+    #[test]
+    fn economic_changes_need_two_admins() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_signer(chain_owner());
+
+        // A single admin may tune operational settings but not economic ones
+        let config = NetworkConfig { max_upload_size: 1_024, ..app.config() };
+        let response = app.execute_operation(Operation::UpdateConfig { config }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::ConfigUpdated));
+        let config = NetworkConfig { min_node_stake: Amount::ONE, ..app.config() };
+        let response = app.execute_operation(Operation::UpdateConfig { config }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let change = EconomicChange { min_node_stake: Some(Amount::ONE), ..EconomicChange::default() };
+        let response = app.execute_operation(Operation::ProposeChange { change }).blocking_wait();
+        let DCDNResponse::ProposalCreated { proposal_id } = response else {
+            panic!("Unexpected proposal response: {response:?}");
+        };
+        let response = app.execute_operation(Operation::ApproveProposal { proposal_id }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        assert_eq!(app.config().min_node_stake, MIN_NODE_STAKE);
+
+        app.runtime.set_authenticated_signer(operator());
+        let response = app.execute_operation(Operation::ApproveProposal { proposal_id }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(second_admin());
+        let response = app.execute_operation(Operation::ApproveProposal { proposal_id }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::ProposalApproved { executed: true }));
+        assert_eq!(app.config().min_node_stake, Amount::ONE);
+        assert_eq!(app.config().max_upload_size, 1_024);
+    }
+
+    #[test]
+    fn creator_chain_owners_govern_without_admins() {
+        assert_eq!([1, 2, 3, 4, 5].map(required_approvals), [1, 2, 2, 3, 3]);
+
+        let mut app = create_and_instantiate_app_with(NetworkConfig::default());
+        let response = app.execute_operation(Operation::Pause { target: PauseTarget::Uploads }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        // The creator chain's sole owner stands in for the admins, so their proposals pass alone
+        app.runtime.set_authenticated_signer(chain_owner());
+        let change = EconomicChange { min_node_stake: Some(Amount::ONE), ..EconomicChange::default() };
+        let response = app.execute_operation(Operation::ProposeChange { change }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::ProposalCreated { .. }));
+        assert_eq!(app.config().min_node_stake, Amount::ONE);
+
+        let change = EconomicChange { admins: Some(Vec::new()), ..EconomicChange::default() };
+        let response = app.execute_operation(Operation::ProposeChange { change }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        let change = EconomicChange { admins: Some(vec![second_admin()]), ..EconomicChange::default() };
+        let response = app.execute_operation(Operation::ProposeChange { change }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::ProposalCreated { .. }));
+        assert_eq!(app.config().admins, vec![second_admin()]);
+        let response = app.execute_operation(Operation::Pause { target: PauseTarget::Uploads }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+    }
+
+    #[test]
+    fn paused_uploads_and_registrations_are_refused() {
+        let mut app = create_and_instantiate_app();
+        let response = app
+            .execute_operation(Operation::Pause { target: PauseTarget::Uploads })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        for target in [PauseTarget::Uploads, PauseTarget::Registrations] {
+            let response = app.execute_operation(Operation::Pause { target }).blocking_wait();
+            assert!(matches!(response, DCDNResponse::PauseUpdated));
+        }
+
+        app.runtime.set_authenticated_signer(operator());
+        let response = app
+            .execute_operation(Operation::Upload { content: b"paused".to_vec(), metadata: metadata(b"paused") })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let response = app
+            .execute_operation(Operation::RegisterNode {
+                node_id: "node-1".to_string(),
+                location: berlin(),
+                capacity: 1_000_000,
+                bandwidth: 10_000,
+                profile: edge_profile(),
+                stake: MIN_NODE_STAKE,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        app.execute_operation(Operation::Unpause { target: PauseTarget::Uploads }).blocking_wait();
+        app.runtime.set_authenticated_signer(operator());
+        upload(&mut app, b"resumed");
+    }
+
+    #[test]
+    fn taken_down_content_is_blocked_until_appeal_is_granted() {
+        let mut app = create_and_instantiate_app();
+        let content_id = upload(&mut app, b"disputed bytes");
+        register_node(&mut app, "node-1");
+        cache(&mut app, &content_id, "node-1");
+
+        let report = |reason: String| Operation::ReportContent { content_id: content_id.clone(), reason };
+        app.runtime.set_authenticated_signer(None);
+        assert!(matches!(app.execute_operation(report("Infringing".to_string())).blocking_wait(), DCDNResponse::Error { .. }));
+        app.runtime.set_authenticated_signer(content_owner());
+        let long_reason = "x".repeat(MAX_REPORT_REASON_LEN + 1);
+        assert!(matches!(app.execute_operation(report(long_reason.clone())).blocking_wait(), DCDNResponse::Error { .. }));
+        assert!(matches!(app.execute_operation(report("Infringing".to_string())).blocking_wait(), DCDNResponse::ContentReported));
+        // A second report waits until moderators have acted on the first
+        assert!(matches!(app.execute_operation(report("Still infringing".to_string())).blocking_wait(), DCDNResponse::Error { .. }));
+        let moderate = |reason: String| Operation::Moderate {
+            content_id: content_id.clone(),
+            action: ModerationAction::TakeDown,
+            reason,
+        };
+        let take_down = || moderate("Confirmed infringement".to_string());
+        assert!(matches!(app.execute_operation(take_down()).blocking_wait(), DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        assert!(matches!(app.execute_operation(moderate(long_reason.clone())).blocking_wait(), DCDNResponse::Error { .. }));
+        assert!(matches!(app.execute_operation(take_down()).blocking_wait(), DCDNResponse::ModerationUpdated));
+        assert!(node_contents(&app, "node-1").is_empty());
+        let download = || Operation::Download { content_id: content_id.clone() };
+        assert!(matches!(app.execute_operation(download()).blocking_wait(), DCDNResponse::Error { .. }));
+        let request_cache = Operation::RequestCache { content_id: content_id.clone(), node_id: "node-1".to_string() };
+        assert!(matches!(app.execute_operation(request_cache).blocking_wait(), DCDNResponse::Error { .. }));
+
+        // The node can neither claim the copy again nor be paid for serving it
+        app.runtime.set_authenticated_signer(operator());
+        let claim = Operation::UpdateAvailability { content_id: content_id.clone(), node_id: "node-1".to_string(), available: true };
+        assert!(matches!(app.execute_operation(claim).blocking_wait(), DCDNResponse::Error { .. }));
+        app.runtime.set_system_time(Timestamp::from(10));
+        let batch = Operation::ReportUsageBatch {
+            node_id: "node-1".to_string(),
+            window_start: 0,
+            window_end: 10,
+            rows: vec![UsageRow { content_id: content_id.clone(), bytes: 1, request_count: 1 }],
+        };
+        assert!(matches!(app.execute_operation(batch).blocking_wait(), DCDNResponse::Error { .. }));
+
+        let appeal = |statement: String| Operation::AppealModeration { content_id: content_id.clone(), statement };
+        assert!(matches!(app.execute_operation(appeal(long_reason.clone())).blocking_wait(), DCDNResponse::Error { .. }));
+        assert!(matches!(app.execute_operation(appeal("I hold the rights".to_string())).blocking_wait(), DCDNResponse::AppealFiled));
+        assert!(matches!(app.execute_operation(appeal("I hold the rights".to_string())).blocking_wait(), DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(second_admin());
+        let resolve = |reason: String| Operation::ResolveAppeal { content_id: content_id.clone(), granted: true, reason };
+        assert!(matches!(app.execute_operation(resolve(long_reason)).blocking_wait(), DCDNResponse::Error { .. }));
+        assert!(matches!(app.execute_operation(resolve("License shown".to_string())).blocking_wait(), DCDNResponse::AppealResolved));
+        assert!(matches!(app.execute_operation(download()).blocking_wait(), DCDNResponse::DownloadSuccess { .. }));
+
+        let kinds = {
+            let history = app.state.moderation_history.try_load_entry(&content_id).blocking_wait().unwrap().unwrap();
+            history.read(..).blocking_wait().unwrap().into_iter().map(|record| record.kind).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            kinds,
+            [
+                ModerationRecordKind::Reported,
+                ModerationRecordKind::TakenDown,
+                ModerationRecordKind::Appealed,
+                ModerationRecordKind::AppealGranted,
+            ]
+        );
+
+        // With the first report acted on, its reporter may report the content again
+        app.runtime.set_authenticated_signer(content_owner());
+        assert!(matches!(app.execute_operation(report("Infringing again".to_string())).blocking_wait(), DCDNResponse::ContentReported));
+    }
+
+    #[test]
+    fn blocklisted_content_cannot_be_uploaded_or_cached() {
+        let mut app = create_and_instantiate_app();
+        let cached_id = upload(&mut app, b"later blocked");
+        register_node(&mut app, "node-1");
+        cache(&mut app, &cached_id, "node-1");
+        let target_chain = ChainId(CryptoHash::test_hash("target chain"));
+        app.execute_operation(Operation::Replicate { content_id: cached_id.clone(), target_chain })
+            .blocking_wait();
+        let node_chain = ChainId(CryptoHash::test_hash("node chain"));
+        for node_id in ["node-8", "node-9"] {
+            let request_remote_cache = Operation::RequestRemoteCache { content_id: cached_id.clone(), node_id: node_id.to_string(), node_chain };
+            assert!(matches!(app.execute_operation(request_remote_cache).blocking_wait(), DCDNResponse::CacheRequestSent));
+        }
+        app.runtime.set_message_is_bouncing(Some(false));
+        app.runtime.set_message_origin_chain_id(node_chain);
+        app.execute_message(Message::CacheConfirmed { content_id: cached_id.clone(), node_id: "node-8".to_string() })
+            .blocking_wait();
+        let blocked_id = format!("{:x}", Sha256::digest(b"known bad"));
+
+        let block = |hashes: Vec<String>| Operation::BlockHashes { hashes, reason: "Known malware".to_string() };
+        let response = app.execute_operation(block(vec![blocked_id.clone()])).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(block(vec![blocked_id.clone(), "not a hash".to_string()])).blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let response = app
+            .execute_operation(block(vec![blocked_id.clone(), cached_id.to_uppercase(), blocked_id.clone()]))
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::BlocklistUpdated { count: 2 }));
+
+        // Blocking withdraws the copies already out there
+        assert!(node_contents(&app, "node-1").is_empty());
+        assert!(app.runtime.created_send_message_requests().iter().any(|request| {
+            request.destination == target_chain
+                && matches!(&request.message, Message::DropReplica { content_id } if *content_id == cached_id)
+        }));
+        // Remote nodes drop the confirmed copy and forget the unanswered request
+        let requests = app.runtime.created_send_message_requests();
+        assert!(requests.iter().any(|request| {
+            request.destination == node_chain
+                && matches!(&request.message, Message::PurgeRequest { node_id, .. } if node_id == "node-8")
+        }));
+        assert!(requests.iter().any(|request| {
+            request.destination == node_chain
+                && matches!(&request.message, Message::CancelCacheRequest { node_id, .. } if node_id == "node-9")
+        }));
+        drop(requests);
+        assert!(app.state.remote_caches.try_load_entry(&cached_id).blocking_wait().unwrap().is_none());
+
+        app.runtime.set_authenticated_signer(operator());
+        let response = app
+            .execute_operation(Operation::Upload { content: b"known bad".to_vec(), metadata: metadata(b"known bad") })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::Error { .. }));
+        let download = Operation::Download { content_id: cached_id.clone() };
+        assert!(matches!(app.execute_operation(download).blocking_wait(), DCDNResponse::Error { .. }));
+        let request_remote_cache = Operation::RequestRemoteCache {
+            content_id: cached_id.clone(),
+            node_id: "node-2".to_string(),
+            node_chain,
+        };
+        assert!(matches!(app.execute_operation(request_remote_cache).blocking_wait(), DCDNResponse::Error { .. }));
+
+        // A node's chain refuses to cache blocklisted content for others
+        app.execute_message(Message::CacheRequest { content_id: cached_id.clone(), node_id: "node-1".to_string(), size: 13 })
+            .blocking_wait();
+        assert!(app.state.cache_requests.get(&0).blocking_wait().unwrap().is_none());
+        assert!(app.runtime.created_send_message_requests().iter().any(|request| {
+            request.destination == node_chain && matches!(&request.message, Message::CacheRejected { .. })
+        }));
+        let request_cache = Operation::RequestCache { content_id: cached_id.clone(), node_id: "node-1".to_string() };
+        assert!(matches!(app.execute_operation(request_cache).blocking_wait(), DCDNResponse::Error { .. }));
+        let claim = Operation::UpdateAvailability { content_id: cached_id.clone(), node_id: "node-1".to_string(), available: true };
+        assert!(matches!(app.execute_operation(claim).blocking_wait(), DCDNResponse::Error { .. }));
+        assert!(node_contents(&app, "node-1").is_empty());
+
+        app.runtime.set_authenticated_signer(chain_owner());
+        let response = app.execute_operation(Operation::UnblockHashes { hashes: vec![cached_id.clone()] }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::BlocklistUpdated { count: 1 }));
+        app.runtime.set_authenticated_signer(operator());
+        cache(&mut app, &cached_id, "node-1");
+        assert_eq!(node_contents(&app, "node-1"), vec![cached_id]);
+    }
+
+    fn upload(app: &mut DCDNContract, content: &[u8]) -> String {
+        match app
+            .execute_operation(Operation::Upload { content: content.to_vec(), metadata: metadata(content) })
+            .blocking_wait()
+        {
+            DCDNResponse::UploadSuccess { content_id } => content_id,
+            response => panic!("Unexpected upload response: {response:?}"),
+        }
+    }
+
+    fn metadata(content: &[u8]) -> ContentMetadata {
+        ContentMetadata {
+            name: "file.bin".to_string(),
+            size: content.len() as u64,
+            content_type: "application/octet-stream".to_string(),
+            owner: "owner".to_string(),
+            created_at: 0,
+            expires_at: None,
+            content_hash: None,
+        }
+    }
+
+    fn register_node(app: &mut DCDNContract, node_id: &str) {
+        let response = app
+            .execute_operation(Operation::RegisterNode {
+                node_id: node_id.to_string(),
+                location: berlin(),
+                capacity: 1_000_000,
+                bandwidth: 10_000,
+                profile: edge_profile(),
+                stake: MIN_NODE_STAKE,
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::NodeRegistered));
+    }
+
+    fn edge_profile() -> NodeProfile {
+        NodeProfile {
+            endpoints: vec![NodeEndpoint {
+                scheme: "https".to_string(),
+                host: "edge.example.com".to_string(),
+                port: 443,
+            }],
+            protocols: vec!["h2".to_string()],
+            max_object_size: 1 << 30,
+            software_version: "1.0.0".to_string(),
+        }
+    }
+
+    fn cache(app: &mut DCDNContract, content_id: &str, node_id: &str) {
+        let response = app
+            .execute_operation(Operation::RequestCache {
+                content_id: content_id.to_string(),
+                node_id: node_id.to_string(),
+            })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::CacheRequestAccepted));
+    }
+
+    fn close_epoch(app: &mut DCDNContract, epoch: u64) {
+        let now = app.runtime.system_time();
+        app.runtime.set_system_time(now.max(Timestamp::from((epoch + 1) * EPOCH_LENGTH_MICROS)));
+        let response = app.execute_operation(Operation::CloseEpoch { epoch }).blocking_wait();
+        assert!(matches!(response, DCDNResponse::EpochClosed { .. }));
+    }
+
+    fn issue_challenge(app: &mut DCDNContract, content_id: &str, node_id: &str) -> (u64, u64, String) {
+        match app
+            .execute_operation(Operation::IssueChallenge {
+                content_id: content_id.to_string(),
+                node_id: node_id.to_string(),
+            })
+            .blocking_wait()
+        {
+            DCDNResponse::ChallengeIssued { challenge_id, chunk_index, nonce } => (challenge_id, chunk_index, nonce),
+            response => panic!("Unexpected challenge response: {response:?}"),
+        }
+    }
+
+    fn report_usage(app: &mut DCDNContract, node_id: &str, content_id: &str, bytes: u64, nonce: u64) {
+        let client = AccountSecretKey::generate();
+        download_as(app, &client, content_id);
+        let response = app
+            .execute_operation(Operation::ReportUsage {
+                node_id: node_id.to_string(),
+                receipts: vec![signed_receipt(&client, node_id, content_id, bytes, nonce)],
+            })
+            .blocking_wait();
+        match response {
+            DCDNResponse::UsageReported => {}
+            // A node's first report waits for review
+            DCDNResponse::UsageFlagged { report_id } => {
+                let signer = app.runtime.authenticated_signer();
+                app.runtime.set_authenticated_signer(chain_owner());
+                let response = app
+                    .execute_operation(Operation::ReviewUsageReport { report_id, approve: true })
+                    .blocking_wait();
+                assert!(matches!(response, DCDNResponse::UsageReportReviewed));
+                app.runtime.set_authenticated_signer(signer);
+            }
+            response => panic!("Unexpected usage response: {response:?}"),
+        }
+    }
+
+    fn download_as(app: &mut DCDNContract, client: &AccountSecretKey, content_id: &str) {
+        let signer = app.runtime.authenticated_signer();
+        app.runtime.set_authenticated_signer(AccountOwner::from(client.public()));
+        let response = app
+            .execute_operation(Operation::Download { content_id: content_id.to_string() })
+            .blocking_wait();
+        assert!(matches!(response, DCDNResponse::DownloadSuccess { .. }));
+        app.runtime.set_authenticated_signer(signer);
+    }
+
+    fn signed_receipt(client: &AccountSecretKey, node_id: &str, content_id: &str, bytes: u64, nonce: u64) -> SignedReceipt {
+        let receipt = DeliveryReceipt {
+            chain_id: ChainId(CryptoHash::test_hash("chain")),
+            application_id: application_id().forget_abi(),
+            content_id: content_id.to_string(),
+            node_id: node_id.to_string(),
+            bytes,
+            nonce,
+        };
+        SignedReceipt::new(receipt, client)
+    }
+
+    fn berlin() -> NodeLocation {
+        NodeLocation {
+            latitude_micros: 52_520_000,
+            longitude_micros: 13_405_000,
+            region: "eu-central".to_string(),
+            country_code: "DE".to_string(),
+        }
+    }
+
+    fn node_event_kinds(app: &DCDNContract, node_id: &str) -> Vec<NodeEventKind> {
+        let events = app.state.node_events.try_load_entry(node_id).blocking_wait().unwrap().unwrap();
+        events.elements().blocking_wait().unwrap().into_iter().map(|event| event.kind).collect()
+    }
+
+    fn node_contents(app: &DCDNContract, node_id: &str) -> Vec<String> {
+        match app.state.node_contents.try_load_entry(node_id).blocking_wait().unwrap() {
+            Some(contents) => contents.indices().blocking_wait().unwrap(),
+            None => Vec::new(),
+        }
+    }
+
+    const OPERATOR_BALANCE: Amount = Amount::from_tokens(100);
+
+    fn operator() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("operator"))
+    }
+
+    fn application_id() -> ApplicationId<dcdn::DCDNAbi> {
+        ApplicationId::new(CryptoHash::test_hash("dcdn")).with_abi()
+    }
+
+    fn application_owner() -> AccountOwner {
+        AccountOwner::from(application_id())
+    }
+
+    fn content_owner() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("content owner"))
+    }
+
+    fn chain_owner() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("chain owner"))
+    }
+
+    fn create_and_instantiate_app() -> DCDNContract {
+        create_and_instantiate_app_with(NetworkConfig {
+            admins: vec![chain_owner(), second_admin()],
+            ..NetworkConfig::default()
+        })
+    }
+
+    fn second_admin() -> AccountOwner {
+        AccountOwner::from(CryptoHash::test_hash("second admin"))
+    }
+
+    fn create_and_instantiate_app_with(config: NetworkConfig) -> DCDNContract {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(DCDNParameters { config, ..DCDNParameters::default() })
+            .with_application_id(application_id())
+            .with_chain_id(ChainId(CryptoHash::test_hash("chain")))
+            .with_application_creator_chain_id(ChainId(CryptoHash::test_hash("chain")))
+            .with_chain_ownership(ChainOwnership::single(chain_owner()))
+            .with_authenticated_signer(operator())
+            .with_owner_balance(operator(), OPERATOR_BALANCE)
+            .with_owner_balance(content_owner(), OPERATOR_BALANCE)
+            .with_owner_balance(application_owner(), Amount::ZERO)
+            .with_block_height(BlockHeight(0))
+            .with_system_time(Timestamp::from(0));
         let mut contract = DCDNContract {
             state: DCDNState::load(runtime.root_view_storage_context())
                 .blocking_wait()
@@ -274,4 +4486,4 @@ mod tests {
 
         contract
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,57 @@
+// `GraphQLMutationRoot` generates one function per operation with a parameter per field
+#![allow(clippy::too_many_arguments)]
+
 use async_graphql::{Request, Response};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{ContractAbi, ServiceAbi},
+    linera_base_types::{
+        AccountOwner, AccountSecretKey, AccountSignature, Amount, ApplicationId, BcsSignable,
+        ChainId, ContractAbi, ServiceAbi,
+    },
 };
 use serde::{Deserialize, Serialize};
 
+/// Default largest upload accepted, in bytes
+pub const DEFAULT_MAX_UPLOAD_SIZE: u64 = 100 * 1024 * 1024;
+/// Default for how long a node may go without a heartbeat before it is considered offline
+pub const LIVENESS_TIMEOUT_MICROS: u64 = 5 * 60 * 1_000_000;
+/// Length of the window over which a node's served bytes are compared to its bandwidth
+pub const USAGE_WINDOW_MICROS: u64 = 60 * 1_000_000;
+/// Default rolling window over which node reliability is computed
+pub const RELIABILITY_WINDOW_MICROS: u64 = 24 * 60 * 60 * 1_000_000;
+/// Longest window node reliability can be computed over; older node history is discarded
+pub const MAX_RELIABILITY_WINDOW_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+/// How long an account must wait before reporting another failure of the same node
+pub const FAILURE_REPORT_INTERVAL_MICROS: u64 = 60 * 60 * 1_000_000;
+/// Default smallest bond a node operator must lock up to register a node
+pub const MIN_NODE_STAKE: Amount = Amount::from_tokens(10);
+/// How long a deregistered node's stake stays slashable before it can be withdrawn
+pub const UNBONDING_PERIOD_MICROS: u64 = 7 * 24 * 60 * 60 * 1_000_000;
+/// Size of the content chunks nodes are challenged to prove they store
+pub const CHALLENGE_CHUNK_SIZE: u64 = 64 * 1024;
+/// How long a node has to answer a storage challenge
+pub const CHALLENGE_RESPONSE_WINDOW_MICROS: u64 = 10 * 60 * 1_000_000;
+/// How long a node is spared further storage challenges after being challenged
+pub const CHALLENGE_INTERVAL_MICROS: u64 = 60 * 60 * 1_000_000;
+/// Share of its stake, in percent, a node loses for failing a storage audit
+pub const FAILED_AUDIT_SLASH_PERCENT: u128 = 10;
+/// Share of its stake, in percent, a node loses for inflating its usage reports
+pub const INFLATED_USAGE_SLASH_PERCENT: u128 = 25;
+/// Price per byte served charged to content owners when no other price is configured
+pub const DEFAULT_PRICE_PER_BYTE: Amount = Amount::from_attos(1_000_000);
+/// Length of the settlement epochs that node earnings accrue to
+pub const EPOCH_LENGTH_MICROS: u64 = 24 * 60 * 60 * 1_000_000;
+/// How many times its historical serving rate a node may claim before a report is flagged
+pub const USAGE_SPIKE_FACTOR: u64 = 10;
+/// How long a node must have been registered before its history is used to judge reports
+pub const USAGE_HISTORY_MIN_MICROS: u64 = 60 * 60 * 1_000_000;
+/// Most bytes a single usage report may credit without an admin's review
+pub const MAX_UNREVIEWED_USAGE_BYTES: u64 = 1024 * 1024 * 1024;
+/// Longest reason a content report, moderation action or appeal may give, in bytes
+pub const MAX_REPORT_REASON_LEN: usize = 1024;
+/// How long nodes have to acknowledge a purge before they are dropped anyway
+pub const PURGE_DEADLINE_MICROS: u64 = 60 * 60 * 1_000_000;
+
 pub struct DCDNAbi;
 
 impl ContractAbi for DCDNAbi {
@@ -24,47 +71,454 @@ pub enum Operation {
         content: Vec<u8>,
         metadata: ContentMetadata,
     },
+    /// Remove content and every node's listing for it; only its owner may delete it
+    DeleteContent {
+        content_id: String,
+    },
     /// Request to cache content on a specific node
     RequestCache {
         content_id: String,
         node_id: String,
     },
+    /// Ask a node registered on another chain to cache content stored on this chain
+    RequestRemoteCache {
+        content_id: String,
+        node_id: String,
+        node_chain: ChainId,
+    },
+    /// Accept or decline a cache request received from another chain
+    RespondCacheRequest {
+        request_id: u64,
+        accept: bool,
+    },
+    /// Ask every node holding some content to drop it; only the content's owner may purge
+    Purge {
+        content_id: String,
+    },
+    /// Confirm a node dropped content it was asked to purge
+    AcknowledgePurge {
+        content_id: String,
+        node_id: String,
+    },
+    /// Drop the nodes that did not acknowledge a purge before its deadline
+    ExpirePurge {
+        content_id: String,
+    },
+    /// Copy content to the application on another chain; only the content's owner may replicate it
+    Replicate {
+        content_id: String,
+        target_chain: ChainId,
+    },
     /// Update content availability information
     UpdateAvailability {
         content_id: String,
         node_id: String,
         available: bool,
     },
-    /// Request content download
+    /// Request content download; a signed download entitles a node to claim one receipt
+    /// from the signer for this content
     Download {
         content_id: String,
     },
     /// Register a new CDN node
     RegisterNode {
         node_id: String,
-        location: String,
+        location: NodeLocation,
         capacity: u64,
+        /// Declared bandwidth in bytes per second
+        bandwidth: u64,
+        profile: NodeProfile,
+        /// Bond transferred from the signer to the application, at least the configured minimum
+        stake: Amount,
     },
-    /// Report bandwidth usage for payment
+    /// Change the storage capacity a node offers
+    UpdateCapacity {
+        node_id: String,
+        capacity: u64,
+    },
+    /// Take a node out of the network and start the unbonding period
+    DeregisterNode {
+        node_id: String,
+    },
+    /// Return a deregistered node's remaining stake to its operator once unbonded
+    WithdrawStake {
+        node_id: String,
+    },
+    /// Update the endpoints and capabilities a node advertises
+    UpdateNode {
+        node_id: String,
+        profile: NodeProfile,
+    },
+    /// Signal that a node is online
+    Heartbeat {
+        node_id: String,
+    },
+    /// Report that a node failed to serve content to a client; must be signed by the client,
+    /// who may report each node once per `FAILURE_REPORT_INTERVAL_MICROS`
+    ReportNodeFailure {
+        node_id: String,
+        content_id: String,
+    },
+    /// Challenge a node to prove it stores a randomly chosen chunk of some content its operator
+    /// claimed; only the content's owner or an admin may challenge, and each node at most once
+    /// per `CHALLENGE_INTERVAL_MICROS`
+    IssueChallenge {
+        content_id: String,
+        node_id: String,
+    },
+    /// Answer a storage challenge with the SHA-256 of the chunk (`chunk_hash`) and of
+    /// the challenge nonce followed by the chunk (`proof`), both hex-encoded
+    RespondChallenge {
+        challenge_id: u64,
+        chunk_hash: String,
+        proof: String,
+    },
+    /// Fail a storage challenge whose response deadline has passed
+    ExpireChallenge {
+        challenge_id: u64,
+    },
+    /// Report bandwidth usage for payment, backed by receipts signed by the downloading clients
     ReportUsage {
         node_id: String,
+        receipts: Vec<SignedReceipt>,
+    },
+    /// Report aggregated usage for a window of time in one operation; windows of the same
+    /// node may not overlap, so a batch cannot be counted twice. Batches carry no receipts,
+    /// so they are credited to the usage counters but never paid from escrow.
+    ReportUsageBatch {
+        node_id: String,
+        window_start: u64,
+        window_end: u64,
+        rows: Vec<UsageRow>,
+    },
+    /// Credit (`approve`) or reject and slash a flagged usage report; admins only
+    ReviewUsageReport {
+        report_id: u64,
+        approve: bool,
+    },
+    /// Move tokens from the signer into their escrow, which pays for delivery of their content
+    DepositEscrow {
+        amount: Amount,
+    },
+    /// Return unspent escrow to the signer
+    WithdrawEscrow {
+        amount: Amount,
+    },
+    /// Report content as abusive; any signed account may report, giving a reason of at most
+    /// `MAX_REPORT_REASON_LEN` bytes, and may not report it again until a moderator acts on it
+    ReportContent {
+        content_id: String,
+        reason: String,
+    },
+    /// Quarantine, take down or restore content; moderators only
+    Moderate {
+        content_id: String,
+        action: ModerationAction,
+        reason: String,
+    },
+    /// Contest a quarantine or takedown; only the content's owner may appeal, once per action
+    AppealModeration {
         content_id: String,
-        bytes_served: u64,
+        statement: String,
     },
-    /// Update content metadata
+    /// Decide a pending appeal, restoring the content if it is granted; moderators only
+    ResolveAppeal {
+        content_id: String,
+        granted: bool,
+        reason: String,
+    },
+    /// Add SHA-256 content hashes to the blocklist, so matching content can no longer be
+    /// uploaded, downloaded or cached and existing copies are withdrawn; admins only
+    BlockHashes {
+        hashes: Vec<String>,
+        reason: String,
+    },
+    /// Remove content hashes from the blocklist; admins only
+    UnblockHashes {
+        hashes: Vec<String>,
+    },
+    /// Replace the network settings on this chain only; admins only, and economic settings
+    /// can only change through a proposal
+    UpdateConfig {
+        config: NetworkConfig,
+    },
+    /// Stop accepting uploads or node registrations on this chain; admins only
+    Pause {
+        target: PauseTarget,
+    },
+    /// Resume uploads or node registrations on this chain; admins only
+    Unpause {
+        target: PauseTarget,
+    },
+    /// Propose a change to economic settings, counting as the proposer's approval; admins only,
+    /// or the creator chain's owners when no admins are configured
+    ProposeChange {
+        change: EconomicChange,
+    },
+    /// Approve a proposal, which takes effect once enough admins have approved it
+    ApproveProposal {
+        proposal_id: u64,
+    },
+    /// Transfer the earnings of the signer's nodes in closed epochs to the signer
+    ClaimEarnings,
+    /// Finalize a past epoch's earnings and make them claimable; anyone may close an epoch
+    /// in which usage was recorded
+    CloseEpoch {
+        epoch: u64,
+    },
+    /// Update content metadata; owner only, and the size, hash, owner and creation time are
+    /// kept as uploaded
     UpdateMetadata {
         content_id: String,
         metadata: ContentMetadata,
     },
 }
 
+/// Application-wide settings, the same on every chain
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+pub struct DCDNParameters {
+    /// Chain keeping the network-wide node registry; the creator chain if unset
+    pub registry_chain: Option<ChainId>,
+    /// Chains the content registry is split across; if empty, every chain keeps its own content
+    pub shard_chains: Vec<ChainId>,
+    /// Network settings each chain starts with
+    pub config: NetworkConfig,
+}
+
+impl DCDNParameters {
+    /// Shard chain owning a content ID, chosen by the ID's first four hex digits
+    pub fn shard_for(&self, content_id: &str) -> Option<ChainId> {
+        if self.shard_chains.is_empty() {
+            return None;
+        }
+        let prefix = content_id
+            .get(..4)
+            .and_then(|prefix| u16::from_str_radix(prefix, 16).ok())
+            .unwrap_or(0);
+        Some(self.shard_chains[usize::from(prefix) % self.shard_chains.len()])
+    }
+}
+
+/// Stream of `ContentUploaded`, `ContentDeleted` and `PurgeRequested` events
+pub const CONTENT_STREAM: &str = "content";
+/// Stream of `AvailabilityChanged` events
+pub const AVAILABILITY_STREAM: &str = "availability";
+/// Stream of `NodeRegistered` events
+pub const NODE_STREAM: &str = "nodes";
+/// Stream of `UsageReported` events
+pub const USAGE_STREAM: &str = "usage";
+
+/// Events the application emits so other chains and indexers can follow changes
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum DCDNEvent {
+    ContentUploaded {
+        content_id: String,
+        size: u64,
+        owner: Option<AccountOwner>,
+    },
+    ContentDeleted {
+        content_id: String,
+    },
+    /// The owner asked every node holding the content to drop it
+    PurgeRequested {
+        content_id: String,
+        deadline: u64,
+    },
+    /// A node started or stopped holding a copy of some content
+    AvailabilityChanged {
+        content_id: String,
+        node_id: String,
+        available: bool,
+    },
+    NodeRegistered {
+        node_id: String,
+        operator: AccountOwner,
+        home_chain: ChainId,
+        region: String,
+        capacity: u64,
+    },
+    /// Usage was credited to a node
+    UsageReported {
+        node_id: String,
+        bytes: u64,
+        requests: u64,
+    },
+}
+
+impl DCDNEvent {
+    /// Name of the stream the event is emitted on
+    pub fn stream_name(&self) -> &'static str {
+        match self {
+            DCDNEvent::ContentUploaded { .. }
+            | DCDNEvent::ContentDeleted { .. }
+            | DCDNEvent::PurgeRequested { .. } => CONTENT_STREAM,
+            DCDNEvent::AvailabilityChanged { .. } => AVAILABILITY_STREAM,
+            DCDNEvent::NodeRegistered { .. } => NODE_STREAM,
+            DCDNEvent::UsageReported { .. } => USAGE_STREAM,
+        }
+    }
+}
+
+/// Messages between the chains content owners upload to and the chains nodes operate on
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Message {
+    /// Asks the node's chain to cache content held on the sending chain
+    CacheRequest {
+        content_id: String,
+        node_id: String,
+        size: u64,
+    },
+    /// The node accepted a cache request and now holds the content
+    CacheConfirmed {
+        content_id: String,
+        node_id: String,
+    },
+    /// The node, or its chain, declined a cache request
+    CacheRejected {
+        content_id: String,
+        node_id: String,
+        reason: String,
+    },
+    /// Withdraws a cache request the node has not answered yet; a request already accepted is
+    /// treated as a `PurgeRequest`
+    CancelCacheRequest {
+        content_id: String,
+        node_id: String,
+    },
+    /// A node's copy of content requested from another chain came or went
+    AvailabilityUpdate {
+        content_id: String,
+        node_id: String,
+        available: bool,
+    },
+    /// Asks the node's chain to drop a copy the sending chain asked it to cache
+    PurgeRequest {
+        content_id: String,
+        node_id: String,
+    },
+    /// The node dropped its copy as asked
+    PurgeAcknowledged {
+        content_id: String,
+        node_id: String,
+    },
+    /// The node missed a purge's deadline, so the sending chain no longer lists its copy
+    PurgeExpired {
+        content_id: String,
+        node_id: String,
+    },
+    /// Carries content to another chain, which checks the bytes against the content ID
+    ReplicateContent {
+        content_id: String,
+        content: Vec<u8>,
+        metadata: ContentMetadata,
+        owner: Option<AccountOwner>,
+    },
+    /// The target chain stored the replicated content
+    ContentReplicated {
+        content_id: String,
+    },
+    /// The target chain refused the replicated content
+    ReplicationFailed {
+        content_id: String,
+        reason: String,
+    },
+    /// Runs a content operation on the shard chain owning its content ID, as the original signer
+    ShardOperation {
+        request_id: u64,
+        operation: Operation,
+    },
+    /// Tells the chain that forwarded a shard operation whether it succeeded
+    ShardOperationResult {
+        request_id: u64,
+        error: Option<String>,
+    },
+    /// Tells a chain holding a replica that the content changed or was removed on the
+    /// sending chain, so the copy must be dropped
+    DropReplica {
+        content_id: String,
+    },
+    /// Asks the shard chain owning some content to replicate it to the sending chain
+    FetchContent {
+        content_id: String,
+    },
+    /// Tells the registry chain about a node registered on the sending chain
+    NodeRegistered {
+        registration: NodeRegistration,
+    },
+    /// Forwards a node's heartbeat to the registry chain
+    NodeHeartbeat {
+        node_id: String,
+    },
+    /// Tells the registry chain a node's storage capacity changed
+    NodeCapacityChanged {
+        node_id: String,
+        capacity: u64,
+    },
+    /// Tells the registry chain a node left the network
+    NodeDeregistered {
+        node_id: String,
+    },
+    /// Tells a node's chain the registry refused the node because another chain registered
+    /// its ID first, so the operator can take back its stake
+    NodeRejected {
+        node_id: String,
+    },
+}
+
+/// What a node declared when it registered
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NodeRegistration {
+    pub node_id: String,
+    pub operator: AccountOwner,
+    pub location: NodeLocation,
+    pub capacity: u64,
+    pub bandwidth: u64,
+    pub profile: NodeProfile,
+    pub stake: Amount,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub enum DCDNResponse {
     UploadSuccess { content_id: String },
+    ContentDeleted,
     DownloadSuccess { content: Vec<u8> },
     CacheRequestAccepted,
+    CacheRequestSent,
+    CacheRequestAnswered,
+    PurgeRequested { deadline: u64 },
+    ReplicationSent,
+    /// The operation was forwarded to the shard chain owning the content. Its outcome can be
+    /// looked up by `request_id`; downloads have none and are answered with a replica.
+    RoutedToShard { shard_chain: ChainId, request_id: Option<u64> },
+    PurgeAcknowledged,
+    /// Node IDs dropped for not acknowledging the purge in time
+    PurgeExpired { node_ids: Vec<String> },
     NodeRegistered,
+    NodeUpdated,
+    NodeDeregistered { withdrawable_at: u64 },
+    StakeWithdrawn { amount: Amount },
+    HeartbeatRecorded,
+    FailureReported,
+    ChallengeIssued { challenge_id: u64, chunk_index: u64, nonce: String },
+    ChallengePassed,
+    ChallengeFailed,
     UsageReported,
+    UsageFlagged { report_id: u64 },
+    UsageReportReviewed,
+    EscrowUpdated { balance: Amount },
+    ContentReported,
+    ModerationUpdated,
+    AppealFiled,
+    AppealResolved,
+    /// Number of hashes added to or removed from the blocklist
+    BlocklistUpdated { count: u64 },
+    ConfigUpdated,
+    PauseUpdated,
+    ProposalCreated { proposal_id: u64 },
+    /// Whether the approval was the last one needed and the change took effect
+    ProposalApproved { executed: bool },
+    EarningsClaimed { amount: Amount },
+    EpochClosed { payouts: Amount },
     MetadataUpdated,
     Error { message: String },
 }
@@ -79,4 +533,322 @@ pub struct ContentMetadata {
     pub expires_at: Option<u64>,
     /// Hash of the content for integrity verification
     pub content_hash: Option<String>,
-}
\ No newline at end of file
+}
+
+/// Geographic location of a CDN node
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "NodeLocationInput")]
+pub struct NodeLocation {
+    /// Latitude in millionths of a degree, from -90_000_000 to 90_000_000
+    pub latitude_micros: i32,
+    /// Longitude in millionths of a degree, from -180_000_000 to 180_000_000
+    pub longitude_micros: i32,
+    /// Operator-defined region code, e.g. `eu-west`
+    pub region: String,
+    /// ISO 3166-1 alpha-2 country code, e.g. `DE`
+    pub country_code: String,
+}
+
+impl NodeLocation {
+    pub const MAX_LATITUDE_MICROS: i32 = 90_000_000;
+    pub const MAX_LONGITUDE_MICROS: i32 = 180_000_000;
+
+    /// Checks coordinates are in range and codes are well-formed
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-Self::MAX_LATITUDE_MICROS..=Self::MAX_LATITUDE_MICROS).contains(&self.latitude_micros) {
+            return Err("Latitude must be between -90 and 90 degrees".to_string());
+        }
+        if !(-Self::MAX_LONGITUDE_MICROS..=Self::MAX_LONGITUDE_MICROS).contains(&self.longitude_micros) {
+            return Err("Longitude must be between -180 and 180 degrees".to_string());
+        }
+        if self.region.trim().is_empty() {
+            return Err("Region must not be empty".to_string());
+        }
+        if self.country_code.len() != 2 || !self.country_code.bytes().all(|byte| byte.is_ascii_uppercase()) {
+            return Err("Country code must be two uppercase letters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How clients reach a node and what it is able to serve
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "NodeProfileInput")]
+pub struct NodeProfile {
+    pub endpoints: Vec<NodeEndpoint>,
+    /// Delivery protocols the node speaks, e.g. `http/1.1`, `h2`, `h3`
+    pub protocols: Vec<String>,
+    /// Largest object the node will serve, in bytes
+    pub max_object_size: u64,
+    pub software_version: String,
+}
+
+/// A network address at which a node serves content
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "NodeEndpointInput")]
+pub struct NodeEndpoint {
+    /// URL scheme, e.g. `https`
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+}
+
+impl NodeProfile {
+    pub const MAX_ENDPOINTS: usize = 8;
+    pub const MAX_PROTOCOLS: usize = 8;
+    pub const MAX_FIELD_LEN: usize = 255;
+
+    /// Checks the profile is complete and bounded in size
+    pub fn validate(&self) -> Result<(), String> {
+        if self.endpoints.is_empty() || self.endpoints.len() > Self::MAX_ENDPOINTS {
+            return Err(format!("A node must advertise between 1 and {} endpoints", Self::MAX_ENDPOINTS));
+        }
+        for endpoint in &self.endpoints {
+            endpoint.validate()?;
+        }
+        if self.protocols.is_empty() || self.protocols.len() > Self::MAX_PROTOCOLS {
+            return Err(format!("A node must support between 1 and {} protocols", Self::MAX_PROTOCOLS));
+        }
+        if self.protocols.iter().any(|protocol| !is_token(protocol, Self::MAX_FIELD_LEN)) {
+            return Err("Protocol names must be non-empty and contain no whitespace".to_string());
+        }
+        if self.max_object_size == 0 {
+            return Err("Max object size must be positive".to_string());
+        }
+        if !is_token(&self.software_version, Self::MAX_FIELD_LEN) {
+            return Err("Software version must be non-empty and contain no whitespace".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl NodeEndpoint {
+    /// Checks the endpoint forms a usable `scheme://host:port` address
+    pub fn validate(&self) -> Result<(), String> {
+        let scheme_is_valid = self.scheme.starts_with(|c: char| c.is_ascii_lowercase())
+            && self.scheme.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+-.".contains(c));
+        if !scheme_is_valid {
+            return Err(format!("Invalid endpoint scheme: {:?}", self.scheme));
+        }
+        if !is_token(&self.host, NodeProfile::MAX_FIELD_LEN) || self.host.contains('/') {
+            return Err(format!("Invalid endpoint host: {:?}", self.host));
+        }
+        if self.port == 0 {
+            return Err("Endpoint port must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn is_token(value: &str, max_len: usize) -> bool {
+    !value.is_empty() && value.len() <= max_len && !value.chars().any(char::is_whitespace)
+}
+
+/// Usage a node claims for one piece of content
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "UsageRowInput")]
+pub struct UsageRow {
+    pub content_id: String,
+    pub bytes: u64,
+    pub request_count: u64,
+}
+
+/// What content owners pay nodes per byte delivered
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "PricingInput")]
+pub struct Pricing {
+    /// Price applied to nodes in regions without their own price
+    pub price_per_byte: Amount,
+    pub region_prices: Vec<RegionPrice>,
+}
+
+/// Price per byte for deliveries by nodes in one region
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "RegionPriceInput")]
+pub struct RegionPrice {
+    pub region: String,
+    pub price_per_byte: Amount,
+}
+
+/// Settings a deployment can tune without changing the code
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "NetworkConfigInput")]
+pub struct NetworkConfig {
+    /// Largest upload accepted, in bytes
+    pub max_upload_size: u64,
+    /// Lifetime given to uploads that do not set `expires_at`; they never expire if unset.
+    /// Expired content is no longer served, cached or routed to.
+    pub default_ttl_micros: Option<u64>,
+    /// How long a node may go without a heartbeat before it is considered offline
+    pub liveness_timeout_micros: u64,
+    /// Price per byte served in regions without their own price
+    pub price_per_byte: Amount,
+    /// Smallest bond a node operator must lock up to register a node
+    pub min_node_stake: Amount,
+    /// Accounts allowed to administer the network; if empty, the owners of the creator chain
+    /// administer it from there
+    pub admins: Vec<AccountOwner>,
+    /// Accounts allowed to moderate content, besides the admins
+    pub moderators: Vec<AccountOwner>,
+    pub node_admission: NodeAdmission,
+}
+
+/// Who may register nodes
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum NodeAdmission {
+    /// Anyone who locks up the minimum stake
+    #[default]
+    Open,
+    /// Only admins
+    Permissioned,
+}
+
+/// What a moderator does to reported content
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum ModerationAction {
+    /// Block the content while it is reviewed
+    Quarantine,
+    /// Block the content and drop every node's copy
+    TakeDown,
+    /// Lift a quarantine or takedown
+    Restore,
+}
+
+/// Activity admins can pause in an emergency
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum PauseTarget {
+    Uploads,
+    Registrations,
+}
+
+/// Economic settings a proposal changes; settings left unset are kept
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject, async_graphql::SimpleObject)]
+#[graphql(input_name = "EconomicChangeInput")]
+pub struct EconomicChange {
+    pub pricing: Option<Pricing>,
+    pub min_node_stake: Option<Amount>,
+    pub admins: Option<Vec<AccountOwner>>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            max_upload_size: DEFAULT_MAX_UPLOAD_SIZE,
+            default_ttl_micros: None,
+            liveness_timeout_micros: LIVENESS_TIMEOUT_MICROS,
+            price_per_byte: DEFAULT_PRICE_PER_BYTE,
+            min_node_stake: MIN_NODE_STAKE,
+            admins: Vec::new(),
+            moderators: Vec::new(),
+            node_admission: NodeAdmission::Open,
+        }
+    }
+}
+
+/// Approvals a proposal needs from `admin_count` admins: a majority, and at least two
+/// unless there is only one admin
+pub fn required_approvals(admin_count: usize) -> usize {
+    (admin_count / 2 + 1).max(2).min(admin_count)
+}
+
+impl NetworkConfig {
+    /// Whether `other` differs in settings that only a proposal may change
+    pub fn economics_differ(&self, other: &NetworkConfig) -> bool {
+        self.price_per_byte != other.price_per_byte
+            || self.min_node_stake != other.min_node_stake
+            || self.admins != other.admins
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_upload_size == 0 {
+            return Err("Maximum upload size must be positive".to_string());
+        }
+        if self.default_ttl_micros == Some(0) {
+            return Err("Default TTL must be positive".to_string());
+        }
+        if self.liveness_timeout_micros == 0 {
+            return Err("Liveness timeout must be positive".to_string());
+        }
+        if self.node_admission == NodeAdmission::Permissioned && self.admins.is_empty() {
+            return Err("Permissioned admission needs at least one admin".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Pricing {
+            price_per_byte: DEFAULT_PRICE_PER_BYTE,
+            region_prices: Vec::new(),
+        }
+    }
+}
+
+impl Pricing {
+    /// Price per byte for a node in `region`
+    pub fn price_for(&self, region: &str) -> Amount {
+        self.region_prices
+            .iter()
+            .find(|region_price| region_price.region == region)
+            .map_or(self.price_per_byte, |region_price| region_price.price_per_byte)
+    }
+
+    /// Checks each region is priced at most once
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, region_price) in self.region_prices.iter().enumerate() {
+            if region_price.region.trim().is_empty() {
+                return Err("Region must not be empty".to_string());
+            }
+            if self.region_prices[..index].iter().any(|other| other.region == region_price.region) {
+                return Err(format!("Region {:?} is priced more than once", region_price.region));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A client's acknowledgement that a node delivered some bytes of content to it
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, async_graphql::InputObject)]
+pub struct DeliveryReceipt {
+    /// Chain and application the receipt is valid on, so it cannot be replayed elsewhere
+    pub chain_id: ChainId,
+    pub application_id: ApplicationId,
+    pub content_id: String,
+    pub node_id: String,
+    pub bytes: u64,
+    /// Chosen by the client; each (client, nonce) pair can be claimed only once
+    pub nonce: u64,
+}
+
+impl BcsSignable<'_> for DeliveryReceipt {}
+
+/// A delivery receipt with the client's signature over it
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::InputObject)]
+pub struct SignedReceipt {
+    pub receipt: DeliveryReceipt,
+    /// Hex-encoded BCS serialization of the client's `AccountSignature`
+    pub signature: String,
+}
+
+impl SignedReceipt {
+    /// Signs a receipt on behalf of a client
+    pub fn new(receipt: DeliveryReceipt, secret: &AccountSecretKey) -> Self {
+        let signature = hex::encode(secret.sign(&receipt).to_bytes());
+        SignedReceipt { receipt, signature }
+    }
+
+    /// Checks the receipt was made for this chain and application and returns the account
+    /// of the client that signed it
+    pub fn verify(&self, chain_id: ChainId, application_id: ApplicationId) -> Result<AccountOwner, String> {
+        if self.receipt.chain_id != chain_id || self.receipt.application_id != application_id {
+            return Err("Receipt was issued for a different chain or application".to_string());
+        }
+        let bytes = hex::decode(&self.signature).map_err(|_| "Receipt signature is not valid hex".to_string())?;
+        let signature = AccountSignature::from_slice(&bytes).map_err(|error| format!("Invalid receipt signature: {error}"))?;
+        signature
+            .verify(&self.receipt)
+            .map_err(|error| format!("Receipt signature does not match: {error}"))?;
+        Ok(signature.owner())
+    }
+}
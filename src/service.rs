@@ -2,17 +2,27 @@
 
 mod state;
 
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
 
 use async_graphql::{EmptySubscription, Object, Schema};
 use linera_sdk::{
-    graphql::GraphQLMutationRoot, linera_base_types::WithServiceAbi, views::View, Service,
-    ServiceRuntime,
+    bcs,
+    graphql::GraphQLMutationRoot,
+    linera_base_types::{AccountOwner, Amount, ChainId, WithServiceAbi},
+    views::View,
+    Service, ServiceRuntime,
 };
 
-use dcdn::Operation;
+use dcdn::{
+    NetworkConfig, NodeLocation, NodeProfile, Operation, Pricing, EPOCH_LENGTH_MICROS,
+    MAX_RELIABILITY_WINDOW_MICROS, RELIABILITY_WINDOW_MICROS, USAGE_WINDOW_MICROS,
+};
 
-use self::state::DCDNState;
+use self::state::{
+    BlockedHash, ContentModeration, ContentStats, DCDNState, EpochSummary, FlaggedReport,
+    IncomingCacheRequest, ModerationRecord, ModerationStatus, NodeEventKind, NodeInfo, NodeStatement,
+    OwnerStatement, Pauses, Proposal, Purge, RemoteCache, Replication, ShardRequest, StorageChallenge,
+};
 
 pub struct DCDNService {
     state: Arc<DCDNState>,
@@ -26,7 +36,7 @@ impl WithServiceAbi for DCDNService {
 }
 
 impl Service for DCDNService {
-    type Parameters = ();
+    type Parameters = dcdn::DCDNParameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = DCDNState::load(runtime.root_view_storage_context())
@@ -45,6 +55,7 @@ impl Service for DCDNService {
             EmptySubscription,
         )
         .data(Arc::clone(&self.state))
+        .data(Arc::clone(&self.runtime))
         .finish()
         .execute(query)
         .await
@@ -57,7 +68,121 @@ struct QueryRoot;
 impl QueryRoot {
     async fn content_exists(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> bool {
         let state = ctx.data::<Arc<DCDNState>>().unwrap();
-        state.content_map.get(&content_id).await.unwrap().is_some()
+        state.content_map.contains_key(&content_id).await.unwrap()
+    }
+
+    /// Chain keeping the network-wide node registry and totals
+    async fn registry_chain(&self, ctx: &async_graphql::Context<'_>) -> ChainId {
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        runtime
+            .application_parameters()
+            .registry_chain
+            .unwrap_or_else(|| runtime.application_creator_chain_id())
+    }
+
+    /// Network settings in effect on this chain
+    async fn config(&self, ctx: &async_graphql::Context<'_>) -> NetworkConfig {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        network_config(state, runtime)
+    }
+
+    async fn moderation(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Option<ContentModeration> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.moderation.get(&content_id).await.unwrap()
+    }
+
+    /// Reports, moderation actions and appeals for some content, oldest first
+    async fn moderation_history(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Vec<ModerationRecord> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        match state.moderation_history.try_load_entry(&content_id).await.unwrap() {
+            Some(history) => history.read(..).await.unwrap(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Blocklisted content hashes in hash order, paginated by cursor so edge nodes can sync
+    /// the list
+    async fn blocklist(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        cursor: Option<String>,
+        limit: Option<i32>,
+    ) -> BlocklistPage {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let limit = limit.unwrap_or(1000).clamp(1, 10_000) as usize;
+
+        // Blocklisted hashes are normalized to the same length, so the map's key order is
+        // hash order and the page ends as soon as it is full
+        let mut entries = Vec::<BlockedHash>::new();
+        let mut next_cursor = None;
+        state
+            .blocklist
+            .for_each_index_value_while(|hash, entry| {
+                if cursor.as_ref().is_some_and(|cursor| hash <= *cursor) {
+                    return Ok(true);
+                }
+                if entries.len() == limit {
+                    next_cursor = entries.last().map(|entry| entry.hash.clone());
+                    return Ok(false);
+                }
+                entries.push(entry.into_owned());
+                Ok(true)
+            })
+            .await
+            .unwrap();
+
+        BlocklistPage { entries, next_cursor }
+    }
+
+    async fn is_blocklisted(&self, ctx: &async_graphql::Context<'_>, hash: String) -> bool {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.blocklist.contains_key(&hash.to_ascii_lowercase()).await.unwrap()
+    }
+
+    /// Activity admins have paused on this chain
+    async fn pauses(&self, ctx: &async_graphql::Context<'_>) -> Pauses {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.pauses.get().clone()
+    }
+
+    async fn proposal(&self, ctx: &async_graphql::Context<'_>, proposal_id: u64) -> Option<Proposal> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.proposals.get(&proposal_id).await.unwrap()
+    }
+
+    /// Proposed economic changes, optionally only those still awaiting approval
+    async fn proposals(&self, ctx: &async_graphql::Context<'_>, open_only: Option<bool>) -> Vec<Proposal> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let open_only = open_only.unwrap_or(false);
+        let mut proposals = Vec::new();
+        state
+            .proposals
+            .for_each_index_value(|_, proposal| {
+                if !open_only || proposal.executed_at.is_none() {
+                    proposals.push(proposal.into_owned());
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        proposals
+    }
+
+    /// Chain a client should ask about some content: its shard chain, or this chain if the
+    /// content registry is not sharded
+    async fn shard_chain(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> ChainId {
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        runtime
+            .application_parameters()
+            .shard_for(&content_id)
+            .unwrap_or_else(|| runtime.chain_id())
+    }
+
+    /// Outcome of an operation this chain forwarded to a shard chain
+    async fn shard_request(&self, ctx: &async_graphql::Context<'_>, request_id: u64) -> Option<ShardRequest> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.shard_requests.get(&request_id).await.unwrap()
     }
 
     async fn get_node_count(&self, ctx: &async_graphql::Context<'_>) -> u64 {
@@ -75,19 +200,151 @@ impl QueryRoot {
         *state.total_data_served.get()
     }
 
+    async fn get_slashed_stake(&self, ctx: &async_graphql::Context<'_>) -> Amount {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        *state.slashed_stake.get()
+    }
+
+    async fn pricing(&self, ctx: &async_graphql::Context<'_>) -> Pricing {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.pricing.get().clone()
+    }
+
+    /// Unspent escrow of a content owner
+    async fn escrow_balance(&self, ctx: &async_graphql::Context<'_>, owner: AccountOwner) -> Amount {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.escrow.get(&owner).await.unwrap().unwrap_or_default()
+    }
+
+    /// Earnings a node operator has yet to claim
+    async fn unclaimed_earnings(&self, ctx: &async_graphql::Context<'_>, operator: AccountOwner) -> Amount {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.earnings.get(&operator).await.unwrap().unwrap_or_default()
+    }
+
+    async fn current_epoch(&self, ctx: &async_graphql::Context<'_>) -> u64 {
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        runtime.system_time().micros() / EPOCH_LENGTH_MICROS
+    }
+
+    async fn epoch(&self, ctx: &async_graphql::Context<'_>, epoch: u64) -> Option<EpochSummary> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.epochs.get(&epoch).await.unwrap()
+    }
+
+    /// What a node served and earned in each epoch it was active, oldest first
+    async fn node_statements(&self, ctx: &async_graphql::Context<'_>, node_id: String) -> Vec<NodeStatement> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        match state.node_statements.try_load_entry(&node_id).await.unwrap() {
+            Some(statements) => statements.index_values().await.unwrap().into_iter().map(|(_, statement)| statement).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// What a content owner had delivered and paid in each epoch, oldest first
+    async fn owner_statements(&self, ctx: &async_graphql::Context<'_>, owner: AccountOwner) -> Vec<OwnerStatement> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        match state.owner_statements.try_load_entry(&owner).await.unwrap() {
+            Some(statements) => statements.index_values().await.unwrap().into_iter().map(|(_, statement)| statement).collect(),
+            None => Vec::new(),
+        }
+    }
+
     async fn get_node(&self, ctx: &async_graphql::Context<'_>, node_id: String) -> Option<NodeQuery> {
         let state = ctx.data::<Arc<DCDNState>>().unwrap();
-        match state.nodes.get(&node_id).await.unwrap() {
-            Some(node_info) => Some(NodeQuery {
-                id: node_info.id,
-                location: node_info.location,
-                capacity: node_info.capacity,
-                used_capacity: node_info.used_capacity,
-                available: node_info.available,
-                data_served: node_info.data_served,
-            }),
+        state.nodes.get(&node_id).await.unwrap().map(NodeQuery::from)
+    }
+
+    /// Lists registered nodes matching `filter`, in registry key order or sorted, paginated by
+    /// a cursor naming the last node returned
+    async fn nodes(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        filter: Option<NodeFilter>,
+        sort: Option<NodeSort>,
+        cursor: Option<String>,
+        limit: Option<i32>,
+    ) -> async_graphql::Result<NodePage> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        let liveness_timeout = network_config(state, runtime).liveness_timeout_micros;
+        let now = runtime.system_time().micros();
+        let filter = filter.unwrap_or_default();
+        let limit = limit.unwrap_or(50).clamp(1, 500) as usize;
+
+        let Some(sort) = sort else {
+            // Walk the registry in key order from the cursor, a batch at a time, until the page
+            // is full or the registry is exhausted
+            let mut nodes = Vec::new();
+            let mut after = cursor;
+            loop {
+                let after_key = after.as_ref().map(|node_id| bcs::to_bytes(node_id).unwrap());
+                let mut batch = Vec::new();
+                let mut exhausted = true;
+                state
+                    .nodes
+                    .for_each_index_value_while(|node_id, node_info| {
+                        if after_key.as_ref().is_some_and(|after_key| bcs::to_bytes(&node_id).unwrap() <= *after_key) {
+                            return Ok(true);
+                        }
+                        if batch.len() + nodes.len() == limit {
+                            exhausted = false;
+                            return Ok(false);
+                        }
+                        batch.push(node_info.into_owned());
+                        Ok(true)
+                    })
+                    .await
+                    .unwrap();
+                after = batch.last().map(|node_info: &NodeInfo| node_info.id.clone()).or(after);
+                for node_info in batch {
+                    if filter.accepts(state, &node_info, now, liveness_timeout).await {
+                        nodes.push(NodeQuery::from(node_info));
+                    }
+                }
+                if exhausted {
+                    return Ok(NodePage { nodes, next_cursor: None });
+                }
+                if nodes.len() == limit {
+                    return Ok(NodePage { nodes, next_cursor: after });
+                }
+            }
+        };
+
+        // Sorted listings rank every matching node; the cursor is the last node's sort key and
+        // ID, so nodes registering or retiring between pages do not shift the listing
+        let after = match cursor {
+            Some(cursor) => {
+                let (key, node_id) = cursor.split_once('/').ok_or("Invalid cursor")?;
+                Some((key.parse::<u64>().map_err(|_| "Invalid cursor")?, node_id.to_string()))
+            }
             None => None,
+        };
+        let mut matching = Vec::new();
+        for (_, node_info) in state.nodes.index_values().await.unwrap() {
+            if filter.accepts(state, &node_info, now, liveness_timeout).await {
+                matching.push((sort.key(&node_info), node_info));
+            }
         }
+        matching.sort_by(|(a_key, a), (b_key, b)| b_key.cmp(a_key).then_with(|| a.id.cmp(&b.id)));
+        let mut page = matching
+            .into_iter()
+            .filter(|(key, node_info)| {
+                after.as_ref().is_none_or(|(after_key, after_id)| {
+                    key < after_key || (key == after_key && node_info.id > *after_id)
+                })
+            })
+            .take(limit + 1)
+            .collect::<Vec<_>>();
+        let next_cursor = if page.len() > limit {
+            page.truncate(limit);
+            page.last().map(|(key, node_info)| format!("{key}/{}", node_info.id))
+        } else {
+            None
+        };
+        let nodes = page.into_iter().map(|(_, node_info)| NodeQuery::from(node_info)).collect();
+
+        Ok(NodePage { nodes, next_cursor })
     }
 
     async fn get_content_metadata(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Option<ContentMetadataQuery> {
@@ -107,38 +364,337 @@ impl QueryRoot {
         }
     }
 
+    async fn content_stats(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Option<ContentStats> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.content_stats.get(&content_id).await.unwrap()
+    }
+
     async fn get_content_nodes(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Vec<String> {
         let state = ctx.data::<Arc<DCDNState>>().unwrap();
-        state.content_availability.get(&content_id).await.unwrap().unwrap_or_default()
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        if is_blocked(state, &content_id).await || is_expired(state, &content_id, runtime.system_time().micros()).await {
+            return Vec::new();
+        }
+        match state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders.indices().await.unwrap(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Lists the nodes holding some content with their endpoints and capabilities
+    async fn get_content_node_details(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Vec<NodeQuery> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        if is_blocked(state, &content_id).await || is_expired(state, &content_id, runtime.system_time().micros()).await {
+            return Vec::new();
+        }
+        let node_ids = match state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders.indices().await.unwrap(),
+            None => return Vec::new(),
+        };
+
+        let mut nodes = Vec::new();
+        for node_id in node_ids {
+            if let Some(node_info) = state.nodes.get(&node_id).await.unwrap() {
+                nodes.push(NodeQuery::from(node_info));
+            }
+        }
+        nodes
+    }
+
+    /// Ranks the nodes holding some content by great-circle distance from a client
+    async fn nearest_nodes(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        content_id: String,
+        latitude: f64,
+        longitude: f64,
+        limit: Option<i32>,
+    ) -> Vec<NearbyNodeQuery> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        let limit = limit.unwrap_or(10).clamp(1, 100) as usize;
+        if is_blocked(state, &content_id).await || is_expired(state, &content_id, runtime.system_time().micros()).await {
+            return Vec::new();
+        }
+        let node_ids = match state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders.indices().await.unwrap(),
+            None => return Vec::new(),
+        };
+
+        let mut nearby = Vec::new();
+        for node_id in node_ids {
+            let Some(node_info) = state.nodes.get(&node_id).await.unwrap() else {
+                continue;
+            };
+            let distance_km = great_circle_distance_km(
+                latitude,
+                longitude,
+                micros_to_degrees(node_info.location.latitude_micros),
+                micros_to_degrees(node_info.location.longitude_micros),
+            );
+            nearby.push(NearbyNodeQuery { node: NodeQuery::from(node_info), distance_km });
+        }
+        nearby.sort_by(|a, b| {
+            a.distance_km
+                .total_cmp(&b.distance_km)
+                .then_with(|| a.node.id.cmp(&b.node.id))
+        });
+        nearby.truncate(limit);
+        nearby
+    }
+
+    /// Ranks the nodes holding some content for a client in `client_region`, best candidate
+    /// first, so the client can fail over down the list. Proximity is scored by distance when
+    /// the client gives its coordinates, and by region otherwise.
+    async fn route(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        content_id: String,
+        client_region: String,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        limit: Option<i32>,
+    ) -> Vec<RouteCandidateQuery> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        let liveness_timeout = network_config(state, runtime).liveness_timeout_micros;
+        let now = runtime.system_time().micros();
+        let limit = limit.unwrap_or(10).clamp(1, 100) as usize;
+        if is_blocked(state, &content_id).await || is_expired(state, &content_id, now).await {
+            return Vec::new();
+        }
+        let node_ids = match state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders.indices().await.unwrap(),
+            None => return Vec::new(),
+        };
+
+        let mut candidates = Vec::new();
+        for node_id in node_ids {
+            let Some(node_info) = state.nodes.get(&node_id).await.unwrap() else {
+                continue;
+            };
+            let reliability = node_reliability(state, &node_info, now, RELIABILITY_WINDOW_MICROS, liveness_timeout).await;
+            let distance_km = latitude.zip(longitude).map(|(latitude, longitude)| {
+                great_circle_distance_km(
+                    latitude,
+                    longitude,
+                    micros_to_degrees(node_info.location.latitude_micros),
+                    micros_to_degrees(node_info.location.longitude_micros),
+                )
+            });
+            let same_region = node_info.location.region == client_region;
+            candidates.push(RouteCandidateQuery::evaluate(&node_info, distance_km, same_region, reliability.score, now, liveness_timeout));
+        }
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        candidates.truncate(limit);
+        candidates
+    }
+
+    /// Lists the nodes holding a copy of some content along with when each claimed it
+    async fn content_replicas(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Vec<ReplicaQuery> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let mut replicas = match state.content_availability.try_load_entry(&content_id).await.unwrap() {
+            Some(holders) => holders
+                .index_values()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(node_id, entry)| ReplicaQuery {
+                    node_id,
+                    node_chain: None,
+                    claimed_at: entry.claimed_at,
+                    updated_at: entry.updated_at,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        if let Some(requests) = state.remote_caches.try_load_entry(&content_id).await.unwrap() {
+            for ((node_chain, node_id), remote_cache) in requests.index_values().await.unwrap() {
+                if let Some(copy) = remote_cache.copy {
+                    replicas.push(ReplicaQuery {
+                        node_id,
+                        node_chain: Some(node_chain),
+                        claimed_at: copy.claimed_at,
+                        updated_at: copy.updated_at,
+                    });
+                }
+            }
+        }
+        replicas
+    }
+
+    /// Lists the content IDs held by a node in key order, paginated by cursor
+    async fn node_contents(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        node_id: String,
+        cursor: Option<String>,
+        limit: Option<i32>,
+    ) -> NodeContentsPage {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let limit = limit.unwrap_or(100).clamp(1, 1000) as usize;
+
+        // Content IDs are read in the set's key order, the order of their encodings, which is
+        // ID order for the fixed-length hashes, and the page ends as soon as it is full
+        let cursor_key = cursor.as_ref().map(|cursor| bcs::to_bytes(cursor).unwrap());
+        let mut content_ids = Vec::<String>::new();
+        let mut next_cursor = None;
+        if let Some(contents) = state.node_contents.try_load_entry(&node_id).await.unwrap() {
+            contents
+                .for_each_index_while(|content_id| {
+                    if cursor_key.as_ref().is_some_and(|cursor_key| bcs::to_bytes(&content_id).unwrap() <= *cursor_key) {
+                        return Ok(true);
+                    }
+                    if content_ids.len() == limit {
+                        next_cursor = content_ids.last().cloned();
+                        return Ok(false);
+                    }
+                    content_ids.push(content_id);
+                    Ok(true)
+                })
+                .await
+                .unwrap();
+        }
+
+        NodeContentsPage { node_id, content_ids, next_cursor }
+    }
+
+    /// Cache requests this chain sent to nodes on other chains for some content
+    async fn remote_caches(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Vec<RemoteCache> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        match state.remote_caches.try_load_entry(&content_id).await.unwrap() {
+            Some(requests) => requests.index_values().await.unwrap().into_iter().map(|(_, remote_cache)| remote_cache).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Cache requests from other chains awaiting an answer, optionally only those for one node
+    async fn cache_requests(&self, ctx: &async_graphql::Context<'_>, node_id: Option<String>) -> Vec<IncomingCacheRequest> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let mut requests = Vec::new();
+        state
+            .cache_requests
+            .for_each_index_value(|_, request| {
+                if node_id.as_ref().is_none_or(|node_id| *node_id == request.node_id) {
+                    requests.push(request.into_owned());
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        requests
+    }
+
+    /// Copies of some content sent to other chains
+    async fn replications(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Vec<Replication> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        match state.replications.try_load_entry(&content_id).await.unwrap() {
+            Some(replications) => replications.index_values().await.unwrap().into_iter().map(|(_, replication)| replication).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The latest purge of some content and each node's acknowledgement
+    async fn purge(&self, ctx: &async_graphql::Context<'_>, content_id: String) -> Option<Purge> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.purges.get(&content_id).await.unwrap()
+    }
+
+    /// Copies other chains asked this chain's nodes to purge, awaiting `AcknowledgePurge`
+    async fn pending_purges(&self, ctx: &async_graphql::Context<'_>, node_id: Option<String>) -> Vec<PendingPurge> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state
+            .pending_purges
+            .indices()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|(_, purge_node_id)| node_id.as_ref().is_none_or(|node_id| node_id == purge_node_id))
+            .map(|(content_id, node_id)| PendingPurge { content_id, node_id })
+            .collect()
+    }
+
+    async fn challenge(&self, ctx: &async_graphql::Context<'_>, challenge_id: u64) -> Option<StorageChallenge> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.challenges.get(&challenge_id).await.unwrap()
+    }
+
+    /// Storage challenges a node still has to answer
+    async fn pending_challenges(&self, ctx: &async_graphql::Context<'_>, node_id: String) -> Vec<StorageChallenge> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let challenge_ids = match state.pending_challenges.try_load_entry(&node_id).await.unwrap() {
+            Some(pending) => pending.indices().await.unwrap(),
+            None => return Vec::new(),
+        };
+
+        let mut challenges = Vec::new();
+        for challenge_id in challenge_ids {
+            if let Some(challenge) = state.challenges.get(&challenge_id).await.unwrap() {
+                challenges.push(challenge);
+            }
+        }
+        challenges
+    }
+
+    async fn flagged_report(&self, ctx: &async_graphql::Context<'_>, report_id: u64) -> Option<FlaggedReport> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        state.flagged_reports.get(&report_id).await.unwrap()
+    }
+
+    /// Usage reports awaiting review, optionally only those of one node
+    async fn flagged_reports(&self, ctx: &async_graphql::Context<'_>, node_id: Option<String>) -> Vec<FlaggedReport> {
+        let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let mut reports = Vec::new();
+        for report_id in state.pending_reviews.indices().await.unwrap() {
+            if let Some(report) = state.flagged_reports.get(&report_id).await.unwrap() {
+                if node_id.as_ref().is_none_or(|node_id| *node_id == report.node_id) {
+                    reports.push(report);
+                }
+            }
+        }
+        reports
     }
 
     async fn get_popular_content(&self, ctx: &async_graphql::Context<'_>, limit: Option<i32>) -> Vec<ContentMetadataQuery> {
         let _state = ctx.data::<Arc<DCDNState>>().unwrap();
         let _limit = limit.unwrap_or(10) as usize;
-        let content_list = Vec::new();
-        
         // Get all content and sort by access count
         // This is a simplified version - in a real implementation you'd need to iterate through all content
         // For this demo, we'll return an empty list as we can't efficiently iterate all content in this query
-        content_list
+        Vec::new()
     }
 
-    async fn get_node_performance(&self, ctx: &async_graphql::Context<'_>, node_id: String) -> Option<NodePerformanceQuery> {
+    /// Performance metrics for a node, with reliability computed over the trailing
+    /// `window_secs` (24 hours by default, a week at most)
+    async fn get_node_performance(
+        &self,
+        ctx: &async_graphql::Context<'_>,
+        node_id: String,
+        window_secs: Option<u64>,
+    ) -> Option<NodePerformanceQuery> {
         let state = ctx.data::<Arc<DCDNState>>().unwrap();
+        let runtime = ctx.data::<Arc<ServiceRuntime<DCDNService>>>().unwrap();
+        let liveness_timeout = network_config(state, runtime).liveness_timeout_micros;
+        let window = window_secs.map_or(RELIABILITY_WINDOW_MICROS, |secs| {
+            secs.saturating_mul(1_000_000).min(MAX_RELIABILITY_WINDOW_MICROS)
+        });
         match state.nodes.get(&node_id).await.unwrap() {
             Some(node_info) => {
                 // Calculate performance metrics based on data served vs capacity
-                let utilization = if node_info.capacity > 0 {
-                    (node_info.used_capacity as f64 / node_info.capacity as f64) * 100.0
-                } else {
-                    0.0
-                };
+                let utilization = utilization(&node_info) * 100.0;
+                let reliability = node_reliability(state, &node_info, runtime.system_time().micros(), window, liveness_timeout).await;
                 
                 Some(NodePerformanceQuery {
                     node_id,
                     data_served: node_info.data_served,
                     capacity_utilization: utilization,
-                    reliability_score: 100.0, // Placeholder - would be calculated based on availability history
+                    reliability_score: reliability.score,
+                    reliability,
                 })
             },
             None => None,
@@ -149,11 +705,104 @@ impl QueryRoot {
 #[derive(async_graphql::SimpleObject)]
 struct NodeQuery {
     id: String,
-    location: String,
+    operator: AccountOwner,
+    stake: Amount,
+    /// When the stake may be withdrawn, if the node has deregistered
+    unbonding_until: Option<u64>,
+    location: NodeLocation,
     capacity: u64,
     used_capacity: u64,
     available: bool,
     data_served: u64,
+    /// Total paid to the operator for this node's deliveries
+    earned: Amount,
+    profile: NodeProfile,
+}
+
+impl From<NodeInfo> for NodeQuery {
+    fn from(node_info: NodeInfo) -> Self {
+        NodeQuery {
+            id: node_info.id,
+            operator: node_info.operator,
+            stake: node_info.stake,
+            unbonding_until: node_info.unbonding_until,
+            location: node_info.location,
+            capacity: node_info.capacity,
+            used_capacity: node_info.used_capacity,
+            available: node_info.available,
+            data_served: node_info.data_served,
+            earned: node_info.earned,
+            profile: node_info.profile,
+        }
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct NodePage {
+    nodes: Vec<NodeQuery>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page. The next page may be
+    /// empty if no further node matches the filter.
+    next_cursor: Option<String>,
+}
+
+#[derive(Default, async_graphql::InputObject)]
+struct NodeFilter {
+    region: Option<String>,
+    available: Option<bool>,
+    /// Minimum unused storage capacity in bytes
+    min_free_capacity: Option<u64>,
+    /// Minimum reliability score on a 0–100 scale
+    min_reliability: Option<f64>,
+}
+
+impl NodeFilter {
+    /// Checks every criterion except reliability, which needs the node's history
+    fn matches(&self, node_info: &NodeInfo) -> bool {
+        self.region.as_ref().is_none_or(|region| node_info.location.region == *region)
+            && self.available.is_none_or(|available| node_info.available == available)
+            && self.min_free_capacity.is_none_or(|min_free| {
+                node_info.capacity.saturating_sub(node_info.used_capacity) >= min_free
+            })
+    }
+
+    /// Checks every criterion, including reliability
+    async fn accepts(&self, state: &DCDNState, node_info: &NodeInfo, now: u64, liveness_timeout: u64) -> bool {
+        if !self.matches(node_info) {
+            return false;
+        }
+        match self.min_reliability {
+            Some(min_reliability) => {
+                let reliability = node_reliability(state, node_info, now, RELIABILITY_WINDOW_MICROS, liveness_timeout).await;
+                reliability.score >= min_reliability
+            }
+            None => true,
+        }
+    }
+}
+
+#[derive(async_graphql::Enum, Clone, Copy, PartialEq, Eq)]
+enum NodeSort {
+    /// Most data served first
+    DataServed,
+    /// Highest capacity utilization first
+    Utilization,
+}
+
+impl NodeSort {
+    /// Key nodes are ranked by, highest first. Utilization is never negative, so the order of
+    /// its bit pattern is its numeric order.
+    fn key(self, node_info: &NodeInfo) -> u64 {
+        match self {
+            NodeSort::DataServed => node_info.data_served,
+            NodeSort::Utilization => utilization(node_info).to_bits(),
+        }
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct NearbyNodeQuery {
+    node: NodeQuery,
+    distance_km: f64,
 }
 
 #[derive(async_graphql::SimpleObject)]
@@ -168,10 +817,253 @@ struct ContentMetadataQuery {
     content_hash: Option<String>,
 }
 
+/// A copy another chain asked one of this chain's nodes to drop
+#[derive(async_graphql::SimpleObject)]
+struct PendingPurge {
+    content_id: String,
+    node_id: String,
+}
+
+/// A routing candidate with the components of its score, each between 0 and 1
+#[derive(async_graphql::SimpleObject)]
+struct RouteCandidateQuery {
+    node_id: String,
+    score: f64,
+    /// 1 if the node is marked available and has sent a recent heartbeat, 0 otherwise
+    liveness: f64,
+    /// Great-circle distance from the client, if it gave its coordinates
+    distance_km: Option<f64>,
+    /// Whether the node is in the client's region
+    same_region: bool,
+    /// 1 next to the client, halving every `PROXIMITY_HALF_DISTANCE_KM` further away; without
+    /// coordinates, 1 in the client's region and 0 elsewhere
+    proximity: f64,
+    /// Share of the node's storage capacity still free
+    load: f64,
+    reliability: f64,
+    /// Share of the node's bandwidth for the current usage window still unused
+    bandwidth: f64,
+}
+
+impl RouteCandidateQuery {
+    const PROXIMITY_WEIGHT: f64 = 0.35;
+    const LOAD_WEIGHT: f64 = 0.2;
+    const RELIABILITY_WEIGHT: f64 = 0.25;
+    const BANDWIDTH_WEIGHT: f64 = 0.2;
+    /// Distance at which a node scores half the proximity of one next to the client
+    const PROXIMITY_HALF_DISTANCE_KM: f64 = 1000.0;
+
+    fn evaluate(
+        node_info: &NodeInfo,
+        distance_km: Option<f64>,
+        same_region: bool,
+        reliability_score: f64,
+        now: u64,
+        liveness_timeout: u64,
+    ) -> Self {
+        let online = now.saturating_sub(node_info.last_heartbeat) <= liveness_timeout;
+        let liveness = if node_info.available && online { 1.0 } else { 0.0 };
+        let proximity = match distance_km {
+            Some(distance_km) => 1.0 / (1.0 + distance_km / Self::PROXIMITY_HALF_DISTANCE_KM),
+            None if same_region => 1.0,
+            None => 0.0,
+        };
+        let load = if node_info.capacity > 0 {
+            1.0 - utilization(node_info)
+        } else {
+            0.0
+        };
+        let reliability = reliability_score / 100.0;
+        let window_bytes = if now.saturating_sub(node_info.window_start) < USAGE_WINDOW_MICROS {
+            node_info.window_bytes
+        } else {
+            0
+        };
+        let window_budget = node_info.bandwidth as f64 * (USAGE_WINDOW_MICROS / 1_000_000) as f64;
+        let bandwidth = if window_budget > 0.0 {
+            (1.0 - window_bytes as f64 / window_budget).max(0.0)
+        } else {
+            0.0
+        };
+
+        let score = liveness
+            * (Self::PROXIMITY_WEIGHT * proximity
+                + Self::LOAD_WEIGHT * load
+                + Self::RELIABILITY_WEIGHT * reliability
+                + Self::BANDWIDTH_WEIGHT * bandwidth);
+
+        RouteCandidateQuery {
+            node_id: node_info.id.clone(),
+            score,
+            liveness,
+            distance_km,
+            same_region,
+            proximity,
+            load,
+            reliability,
+            bandwidth,
+        }
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct ReplicaQuery {
+    node_id: String,
+    /// Chain operating the node, for copies held by nodes on other chains
+    node_chain: Option<ChainId>,
+    claimed_at: u64,
+    updated_at: u64,
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct NodeContentsPage {
+    node_id: String,
+    content_ids: Vec<String>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page. The next page may be
+    /// empty if no further node matches the filter.
+    next_cursor: Option<String>,
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct BlocklistPage {
+    entries: Vec<BlockedHash>,
+    /// Pass as `cursor` to fetch the next page; `None` on the last page. The next page may be
+    /// empty if no further node matches the filter.
+    next_cursor: Option<String>,
+}
+
 #[derive(async_graphql::SimpleObject)]
 struct NodePerformanceQuery {
     node_id: String,
     data_served: u64,
     capacity_utilization: f64,
     reliability_score: f64,
-}
\ No newline at end of file
+    reliability: ReliabilityQuery,
+}
+
+/// Share of a node's storage capacity in use, between 0 and 1
+fn utilization(node_info: &NodeInfo) -> f64 {
+    if node_info.capacity > 0 {
+        node_info.used_capacity as f64 / node_info.capacity as f64
+    } else {
+        0.0
+    }
+}
+
+/// Breakdown of a node's reliability score over a rolling window
+#[derive(async_graphql::SimpleObject)]
+struct ReliabilityQuery {
+    /// Overall score on a 0–100 scale
+    score: f64,
+    window_secs: u64,
+    /// Share of liveness intervals in the window covered by a heartbeat
+    uptime: f64,
+    audits_passed: u64,
+    audits_failed: u64,
+    client_failures: u64,
+    availability_flaps: u64,
+}
+
+const CLIENT_FAILURE_PENALTY: f64 = 5.0;
+const AVAILABILITY_FLAP_PENALTY: f64 = 2.0;
+
+/// Whether some content is blocklisted or moderators have quarantined or taken it down
+async fn is_blocked(state: &DCDNState, content_id: &str) -> bool {
+    if state.blocklist.contains_key(content_id).await.unwrap() {
+        return true;
+    }
+    state
+        .moderation
+        .get(content_id)
+        .await
+        .unwrap()
+        .is_some_and(|moderation| moderation.status != ModerationStatus::Active)
+}
+
+/// Whether some content's `expires_at` has passed
+async fn is_expired(state: &DCDNState, content_id: &str, now: u64) -> bool {
+    state
+        .content_map
+        .get(content_id)
+        .await
+        .unwrap()
+        .and_then(|content_data| content_data.metadata.expires_at)
+        .is_some_and(|expires_at| now >= expires_at)
+}
+
+/// Network settings in effect on the chain: those in state, or the parameters' if the chain
+/// has not run the application yet
+fn network_config(state: &DCDNState, runtime: &ServiceRuntime<DCDNService>) -> NetworkConfig {
+    match state.config.get() {
+        Some(config) => config.clone(),
+        None => runtime.application_parameters().config,
+    }
+}
+
+/// Computes a node's reliability from its recorded history over the `window` ending at `now`,
+/// counting uptime in intervals of `liveness_timeout`.
+///
+/// Uptime and the storage audit pass rate scale the score multiplicatively, while each
+/// client failure report and availability flap subtracts a fixed penalty.
+async fn node_reliability(state: &DCDNState, node_info: &NodeInfo, now: u64, window: u64, liveness_timeout: u64) -> ReliabilityQuery {
+    let window_start = now.saturating_sub(window).max(node_info.created_at);
+    let mut heartbeat_intervals = BTreeSet::new();
+    let (mut audits_passed, mut audits_failed, mut client_failures, mut availability_flaps) = (0, 0, 0, 0);
+
+    if let Some(events) = state.node_events.try_load_entry(&node_info.id).await.unwrap() {
+        // The history is trimmed to the longest window, so it is short enough to read whole
+        for event in events.elements().await.unwrap() {
+            if event.timestamp < window_start {
+                continue;
+            }
+            match event.kind {
+                NodeEventKind::Heartbeat => {
+                    heartbeat_intervals.insert(event.timestamp / liveness_timeout);
+                }
+                NodeEventKind::AuditPassed => audits_passed += 1,
+                NodeEventKind::AuditFailed => audits_failed += 1,
+                NodeEventKind::ClientFailure => client_failures += 1,
+                NodeEventKind::AvailabilityFlap => availability_flaps += 1,
+            }
+        }
+    }
+
+    let expected_intervals = now.saturating_sub(window_start).div_ceil(liveness_timeout);
+    let uptime = if expected_intervals > 0 {
+        (heartbeat_intervals.len() as f64 / expected_intervals as f64).min(1.0)
+    } else {
+        1.0
+    };
+    let audit_pass_rate = if audits_passed + audits_failed > 0 {
+        audits_passed as f64 / (audits_passed + audits_failed) as f64
+    } else {
+        1.0
+    };
+    let penalty = CLIENT_FAILURE_PENALTY * client_failures as f64 + AVAILABILITY_FLAP_PENALTY * availability_flaps as f64;
+
+    ReliabilityQuery {
+        score: (100.0 * uptime * audit_pass_rate - penalty).clamp(0.0, 100.0),
+        window_secs: window / 1_000_000,
+        uptime,
+        audits_passed,
+        audits_failed,
+        client_failures,
+        availability_flaps,
+    }
+}
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+fn micros_to_degrees(micros: i32) -> f64 {
+    micros as f64 / 1_000_000.0
+}
+
+/// Haversine distance between two points given in degrees
+fn great_circle_distance_km(lat_a: f64, lon_a: f64, lat_b: f64, lon_b: f64) -> f64 {
+    let (lat_a, lat_b) = (lat_a.to_radians(), lat_b.to_radians());
+    let delta_lat = lat_b - lat_a;
+    let delta_lon = (lon_b - lon_a).to_radians();
+
+    let h = (delta_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
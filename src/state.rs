@@ -1,45 +1,427 @@
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
+use linera_sdk::views::{
+    linera_views, CollectionView, LogView, MapView, QueueView, RegisterView, RootView, SetView,
+    ViewStorageContext,
+};
 use serde::{Deserialize, Serialize};
 
-use dcdn::ContentMetadata;
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ChainId};
+
+use dcdn::{ContentMetadata, EconomicChange, NetworkConfig, NodeLocation, NodeProfile, Pricing, UsageRow};
 
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct DCDNState {
-    /// Map of content ID to content data
+    /// Network settings, copied from the application parameters when the chain first runs the application
+    pub config: RegisterView<Option<NetworkConfig>>,
+    /// Moderation status of content that has been reported or moderated, by content ID
+    pub moderation: MapView<String, ContentModeration>,
+    /// Reports, moderation actions and appeals for each content ID
+    pub moderation_history: CollectionView<String, LogView<ModerationRecord>>,
+    /// Accounts whose report on a content ID no moderator has acted on yet, by content ID
+    pub open_reports: CollectionView<String, SetView<AccountOwner>>,
+    /// Content hashes that may not be uploaded or cached, by hash
+    pub blocklist: MapView<String, BlockedHash>,
+    /// Activity admins have paused on this chain
+    pub pauses: RegisterView<Pauses>,
+    /// Proposed changes to economic settings, by proposal ID
+    pub proposals: MapView<u64, Proposal>,
+    /// ID to assign to the next proposal
+    pub next_proposal_id: RegisterView<u64>,
+    /// Map of content ID to content metadata
     pub content_map: MapView<String, ContentData>,
-    /// Map of content ID to its availability across nodes
-    pub content_availability: MapView<String, Vec<String>>,
-    /// Map of node ID to node information
+    /// Map of content ID to the raw content bytes
+    pub content_bytes: MapView<String, Vec<u8>>,
+    /// Map of content ID to access statistics
+    pub content_stats: MapView<String, ContentStats>,
+    /// Map of content ID to the nodes holding a copy, keyed by node ID
+    pub content_availability: CollectionView<String, MapView<String, AvailabilityEntry>>,
+    /// Reverse index of node ID to the content IDs it holds
+    pub node_contents: CollectionView<String, SetView<String>>,
+    /// Map of node ID to node information; the registry chain also mirrors nodes registered elsewhere
     pub nodes: MapView<String, NodeInfo>,
-    /// Total number of registered nodes
+    /// Per-node history of events that feed the reliability score, oldest first and trimmed
+    /// to `MAX_RELIABILITY_WINDOW_MICROS`
+    pub node_events: CollectionView<String, QueueView<NodeEvent>>,
+    /// When each account last reported a failure of a node, by node ID
+    pub failure_reports: CollectionView<String, MapView<AccountOwner, u64>>,
+    /// Total number of registered nodes, kept on the registry chain
     pub node_count: RegisterView<u64>,
-    /// Total storage capacity across all nodes
+    /// Total storage capacity across all nodes, kept on the registry chain
     pub total_capacity: RegisterView<u64>,
     /// Total amount of data served (for payment calculations)
     pub total_data_served: RegisterView<u64>,
+    /// Unanswered storage challenges, by challenge ID; resolved challenges are removed
+    pub challenges: MapView<u64, StorageChallenge>,
+    /// When each node was last challenged, by node ID
+    pub last_challenged: MapView<String, u64>,
+    /// ID to assign to the next storage challenge
+    pub next_challenge_id: RegisterView<u64>,
+    /// Map of node ID to the IDs of its unanswered challenges
+    pub pending_challenges: CollectionView<String, SetView<u64>>,
+    /// Delivery receipts already claimed, by signing client and nonce
+    pub used_receipts: SetView<(AccountOwner, u64)>,
+    /// Signed downloads no receipt has been claimed for yet, by client and content ID
+    pub downloads: MapView<(AccountOwner, String), u64>,
+    /// Usage reports held back for review, by report ID
+    pub flagged_reports: MapView<u64, FlaggedReport>,
+    /// ID to assign to the next flagged report
+    pub next_flagged_report_id: RegisterView<u64>,
+    /// IDs of flagged reports awaiting review
+    pub pending_reviews: SetView<u64>,
+    /// Current price per byte served
+    pub pricing: RegisterView<Pricing>,
+    /// Prepaid balances of content owners, held in the application's account
+    pub escrow: MapView<AccountOwner, Amount>,
+    /// Earnings from closed epochs that node operators have yet to claim
+    pub earnings: MapView<AccountOwner, Amount>,
+    /// Totals for each settlement epoch
+    pub epochs: MapView<u64, EpochSummary>,
+    /// Earnings of each operator in epochs not yet closed
+    pub epoch_earnings: CollectionView<u64, MapView<AccountOwner, Amount>>,
+    /// Per-epoch statements of each node, by node ID
+    pub node_statements: CollectionView<String, MapView<u64, NodeStatement>>,
+    /// Per-epoch statements of each content owner
+    pub owner_statements: CollectionView<AccountOwner, MapView<u64, OwnerStatement>>,
+    /// Cache requests sent to nodes on other chains, by content ID, then node chain and node ID.
+    /// Copies held by those nodes are tracked here rather than in `content_availability`, so
+    /// they never mix with a local node that has the same ID.
+    pub remote_caches: CollectionView<String, MapView<(ChainId, String), RemoteCache>>,
+    /// Cache requests received from other chains awaiting the node operator's answer
+    pub cache_requests: MapView<u64, IncomingCacheRequest>,
+    /// ID to assign to the next incoming cache request
+    pub next_cache_request_id: RegisterView<u64>,
+    /// Chains that asked this chain's nodes to cache content, by content ID and node ID
+    pub cache_origins: MapView<(String, String), ChainId>,
+    /// Copies of content sent to other chains, by content ID and target chain
+    pub replications: CollectionView<String, MapView<ChainId, Replication>>,
+    /// Chain each replica stored on this chain was sent from, by content ID
+    pub replica_sources: MapView<String, ChainId>,
+    /// Operations forwarded to shard chains, by request ID
+    pub shard_requests: MapView<u64, ShardRequest>,
+    /// ID to assign to the next operation forwarded to a shard chain
+    pub next_shard_request_id: RegisterView<u64>,
+    /// Latest purge of each content ID, by content ID
+    pub purges: MapView<String, Purge>,
+    /// Purges other chains asked this chain's nodes to carry out, by content ID and node ID
+    pub pending_purges: MapView<(String, String), ChainId>,
+    /// Total stake confiscated from misbehaving nodes
+    pub slashed_stake: RegisterView<Amount>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
 pub struct ContentData {
     pub id: String,
-    #[graphql(skip)]
-    pub content: Vec<u8>,
     pub metadata: ContentMetadata,
     pub created_at: u64,
+    /// Account that uploaded the content, whose escrow pays for its delivery
+    pub owner: Option<AccountOwner>,
+}
+
+/// Access statistics kept apart from metadata so downloads only rewrite this record
+#[derive(Debug, Default, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct ContentStats {
     pub last_accessed: u64,
     /// Number of times this content has been accessed
     pub access_count: u64,
+    /// Bytes of this content nodes have been credited for serving
+    pub bytes_served: u64,
+    /// Requests for this content nodes have been credited for serving
+    pub requests_served: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
 pub struct NodeInfo {
     pub id: String,
-    pub location: String,
+    /// Account that registered the node and controls it
+    pub operator: AccountOwner,
+    /// Chain the node registered on, which holds its stake
+    pub home_chain: ChainId,
+    /// Bond currently held by the application for this node
+    pub stake: Amount,
+    /// When the stake may be withdrawn, set once the node deregisters
+    pub unbonding_until: Option<u64>,
+    pub location: NodeLocation,
     pub capacity: u64,
     pub used_capacity: u64,
     pub available: bool,
     pub created_at: u64,
     /// Amount of data this node has served
     pub data_served: u64,
-}
\ No newline at end of file
+    /// Declared bandwidth in bytes per second
+    pub bandwidth: u64,
+    /// Endpoints and capabilities the node advertises to clients
+    pub profile: NodeProfile,
+    /// Time of the node's last heartbeat
+    pub last_heartbeat: u64,
+    /// Start of the current usage window
+    pub window_start: u64,
+    /// Bytes served since `window_start`
+    pub window_bytes: u64,
+    /// End of the latest usage batch window credited to this node
+    pub reported_until: u64,
+    /// Total paid to the operator for this node's deliveries
+    pub earned: Amount,
+}
+
+/// A node's claim to hold a copy of some content
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct AvailabilityEntry {
+    /// When the node first claimed the copy
+    pub claimed_at: u64,
+    /// When the claim was last confirmed by the node
+    pub updated_at: u64,
+    /// Whether the node's operator claimed the copy, rather than only being asked to cache it
+    pub operator_claimed: bool,
+}
+
+/// Something that happened to a node, recorded for reliability scoring
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NodeEvent {
+    pub kind: NodeEventKind,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum NodeEventKind {
+    Heartbeat,
+    AuditPassed,
+    AuditFailed,
+    /// A client reported failing to fetch content from the node
+    ClientFailure,
+    /// The node withdrew a copy it had claimed
+    AvailabilityFlap,
+}
+
+/// Why a node's stake was slashed
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SlashReason {
+    FailedAudit,
+    InflatedUsage,
+}
+
+/// A request for a node to prove it stores one chunk of some content
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct StorageChallenge {
+    pub id: u64,
+    pub content_id: String,
+    pub node_id: String,
+    /// Index of the `CHALLENGE_CHUNK_SIZE` chunk to prove
+    pub chunk_index: u64,
+    /// Hex-encoded nonce derived from chain state, prepended to the chunk in the proof
+    pub nonce: String,
+    pub issued_at: u64,
+    pub deadline: u64,
+}
+
+/// A usage report that looked implausible and was held back instead of credited
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct FlaggedReport {
+    pub id: u64,
+    pub node_id: String,
+    pub rows: Vec<UsageRow>,
+    /// Whether the usage is backed by client receipts; only receipted usage is paid for
+    pub receipted: bool,
+    /// Why the report was flagged
+    pub reason: String,
+    pub flagged_at: u64,
+    pub status: ReviewStatus,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// Network-wide totals for one settlement epoch
+#[derive(Debug, Default, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct EpochSummary {
+    pub epoch: u64,
+    pub bytes_served: u64,
+    /// Total owed to node operators for the epoch
+    pub payouts: Amount,
+    /// Whether the epoch's earnings have been made claimable
+    pub closed: bool,
+}
+
+/// What a node served and earned in one epoch
+#[derive(Debug, Default, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct NodeStatement {
+    pub epoch: u64,
+    pub bytes_served: u64,
+    pub requests_served: u64,
+    pub earned: Amount,
+}
+
+/// What a content owner had delivered and paid for in one epoch
+#[derive(Debug, Default, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct OwnerStatement {
+    pub epoch: u64,
+    pub bytes_delivered: u64,
+    pub requests: u64,
+    pub spent: Amount,
+}
+
+/// A cache request this chain sent to a node on another chain
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct RemoteCache {
+    pub node_id: String,
+    pub node_chain: ChainId,
+    pub status: RemoteCacheStatus,
+    pub requested_at: u64,
+    /// Why the request was declined, if it was
+    pub reason: Option<String>,
+    /// The node's copy, while its chain reports it as available
+    pub copy: Option<AvailabilityEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum RemoteCacheStatus {
+    Pending,
+    Confirmed,
+    Rejected,
+}
+
+/// A request from another chain for one of this chain's nodes to cache content
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct IncomingCacheRequest {
+    pub id: u64,
+    pub content_id: String,
+    pub node_id: String,
+    pub origin_chain: ChainId,
+    pub size: u64,
+    pub received_at: u64,
+}
+
+/// A copy of some content sent to the application on another chain
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct Replication {
+    pub target_chain: ChainId,
+    pub status: ReplicationStatus,
+    pub requested_at: u64,
+    /// Why the target chain refused the copy, if it did
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum ReplicationStatus {
+    Pending,
+    Replicated,
+    Failed,
+}
+
+/// A content operation forwarded to the shard chain owning the content
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct ShardRequest {
+    pub id: u64,
+    pub shard_chain: ChainId,
+    pub sent_at: u64,
+    pub status: ShardRequestStatus,
+    /// Why the shard chain rejected the operation, if it did
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum ShardRequestStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+/// An owner's request for every node holding some content to drop it
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct Purge {
+    pub content_id: String,
+    pub requested_at: u64,
+    /// When nodes that have not acknowledged may be dropped
+    pub deadline: u64,
+    pub targets: Vec<PurgeTarget>,
+}
+
+/// A node asked to drop its copy in a purge
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct PurgeTarget {
+    pub node_id: String,
+    /// Chain the node holds its copy on
+    pub node_chain: ChainId,
+    pub status: PurgeStatus,
+    /// When the node acknowledged or was dropped
+    pub settled_at: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum PurgeStatus {
+    Pending,
+    Acknowledged,
+    /// The deadline passed without an acknowledgement and the node was dropped
+    Expired,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct Pauses {
+    pub uploads: bool,
+    pub registrations: bool,
+}
+
+/// A change to economic settings awaiting approval by enough admins
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct Proposal {
+    pub id: u64,
+    pub change: EconomicChange,
+    pub proposer: AccountOwner,
+    /// Admins who approved the change, the proposer included
+    pub approvals: Vec<AccountOwner>,
+    pub created_at: u64,
+    /// When the change took effect, once approved
+    pub executed_at: Option<u64>,
+}
+
+/// Where some content stands with the moderators
+#[derive(Debug, Default, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct ContentModeration {
+    pub status: ModerationStatus,
+    pub report_count: u64,
+    /// Whether the owner has appealed the current quarantine or takedown
+    pub appealed: bool,
+    /// Whether that appeal awaits a decision
+    pub appeal_pending: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum ModerationStatus {
+    #[default]
+    Active,
+    Quarantined,
+    TakenDown,
+}
+
+/// One entry in a content's moderation history
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct ModerationRecord {
+    pub kind: ModerationRecordKind,
+    /// Account that reported, moderated or appealed, if the operation was signed
+    pub actor: Option<AccountOwner>,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum ModerationRecordKind {
+    Reported,
+    Quarantined,
+    TakenDown,
+    Restored,
+    Appealed,
+    AppealGranted,
+    AppealDenied,
+}
+
+/// A content hash admins have blocked
+#[derive(Debug, Deserialize, Serialize, Clone, async_graphql::SimpleObject)]
+pub struct BlockedHash {
+    pub hash: String,
+    pub reason: String,
+    pub added_by: AccountOwner,
+    pub added_at: u64,
+}
@@ -0,0 +1,65 @@
+// Integration testing for the events the dcdn application emits.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use dcdn::{
+    ContentMetadata, DCDNAbi, DCDNEvent, DCDNParameters, Operation, CONTENT_STREAM,
+};
+use linera_sdk::{
+    bcs,
+    linera_base_types::{AccountOwner, StreamName},
+    test::TestValidator,
+};
+use sha2::{Digest, Sha256};
+
+/// Uploading and deleting content is published on the content stream
+#[tokio::test(flavor = "multi_thread")]
+async fn content_changes_are_emitted() {
+    let (validator, module_id) = TestValidator::with_current_module::<DCDNAbi, DCDNParameters, ()>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = chain.create_application(module_id, DCDNParameters::default(), (), vec![]).await;
+    let owner = AccountOwner::from(chain.public_key());
+
+    let content = b"Hello, subscribers!".to_vec();
+    let content_id = format!("{:x}", Sha256::digest(&content));
+    let metadata = ContentMetadata {
+        name: "hello.txt".to_string(),
+        size: content.len() as u64,
+        content_type: "text/plain".to_string(),
+        owner: "owner".to_string(),
+        created_at: 0,
+        expires_at: None,
+        content_hash: None,
+    };
+    let certificate = chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::Upload { content: content.clone(), metadata })
+                .with_operation(application_id, Operation::DeleteContent { content_id: content_id.clone() });
+        })
+        .await;
+
+    let events = certificate
+        .inner()
+        .block()
+        .body
+        .events
+        .iter()
+        .flatten()
+        .map(|event| {
+            let value: DCDNEvent = bcs::from_bytes(&event.value).expect("Failed to decode event");
+            (event.stream_id.stream_name.clone(), value)
+        })
+        .collect::<Vec<_>>();
+    let content_stream = StreamName::from(CONTENT_STREAM);
+    assert_eq!(
+        events,
+        vec![
+            (
+                content_stream.clone(),
+                DCDNEvent::ContentUploaded { content_id: content_id.clone(), size: content.len() as u64, owner: Some(owner) },
+            ),
+            (content_stream, DCDNEvent::ContentDeleted { content_id }),
+        ]
+    );
+}
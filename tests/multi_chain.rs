@@ -0,0 +1,359 @@
+// Integration testing for cache requests between content-owner and node chains.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use dcdn::{
+    ContentMetadata, DCDNAbi, DCDNParameters, NodeEndpoint, NodeLocation, NodeProfile, Operation, MIN_NODE_STAKE,
+};
+use linera_sdk::{
+    linera_base_types::{Account, AccountOwner, Amount, ApplicationId},
+    test::{ActiveChain, TestValidator},
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// A node on its own chain accepts a cache request from the content owner's chain, and
+/// later withdraws its copy
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_cache_request_is_confirmed() {
+    let (validator, owner_chain, node_chain, application_id) = setup().await;
+    let content_id = upload(&owner_chain, application_id, b"Hello, remote node!").await;
+    register_node(&validator, &node_chain, application_id, "node-1").await;
+
+    request_remote_cache(&owner_chain, &node_chain, application_id, &content_id, "node-1").await;
+    node_chain.handle_received_messages().await;
+
+    let response = node_chain
+        .graphql_query(application_id, "query { cacheRequests { id contentId nodeId } }")
+        .await
+        .response;
+    assert_eq!(
+        response["cacheRequests"],
+        json!([{ "id": 0, "contentId": content_id, "nodeId": "node-1" }])
+    );
+
+    node_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RespondCacheRequest { request_id: 0, accept: true });
+        })
+        .await;
+    owner_chain.handle_received_messages().await;
+
+    assert_eq!(remote_cache_status(&owner_chain, application_id, &content_id).await, json!("CONFIRMED"));
+    assert_eq!(replicas(&owner_chain, application_id, &content_id).await, json!([{ "nodeId": "node-1" }]));
+
+    // Dropping the copy on the node's chain is reflected on the owner's chain
+    node_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::UpdateAvailability {
+                    content_id: content_id.clone(),
+                    node_id: "node-1".to_string(),
+                    available: false,
+                },
+            );
+        })
+        .await;
+    owner_chain.handle_received_messages().await;
+
+    assert_eq!(replicas(&owner_chain, application_id, &content_id).await, json!([]));
+}
+
+/// Declined requests, and requests for nodes the target chain does not know, are rejected
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_cache_request_is_rejected() {
+    let (validator, owner_chain, node_chain, application_id) = setup().await;
+    let content_id = upload(&owner_chain, application_id, b"Unwanted bytes").await;
+
+    request_remote_cache(&owner_chain, &node_chain, application_id, &content_id, "node-1").await;
+    node_chain.handle_received_messages().await;
+    owner_chain.handle_received_messages().await;
+    assert_eq!(remote_cache_status(&owner_chain, application_id, &content_id).await, json!("REJECTED"));
+
+    register_node(&validator, &node_chain, application_id, "node-1").await;
+    request_remote_cache(&owner_chain, &node_chain, application_id, &content_id, "node-1").await;
+    node_chain.handle_received_messages().await;
+    node_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RespondCacheRequest { request_id: 0, accept: false });
+        })
+        .await;
+    owner_chain.handle_received_messages().await;
+
+    assert_eq!(remote_cache_status(&owner_chain, application_id, &content_id).await, json!("REJECTED"));
+    assert_eq!(replicas(&owner_chain, application_id, &content_id).await, json!([]));
+}
+
+/// A purge reaches the node's chain and the node is dropped once its operator acknowledges
+#[tokio::test(flavor = "multi_thread")]
+async fn purge_is_acknowledged_by_remote_node() {
+    let (validator, owner_chain, node_chain, application_id) = setup().await;
+    let content_id = upload(&owner_chain, application_id, b"Outdated bytes").await;
+    register_node(&validator, &node_chain, application_id, "node-1").await;
+    request_remote_cache(&owner_chain, &node_chain, application_id, &content_id, "node-1").await;
+    node_chain.handle_received_messages().await;
+    node_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RespondCacheRequest { request_id: 0, accept: true });
+        })
+        .await;
+    owner_chain.handle_received_messages().await;
+
+    owner_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::Purge { content_id: content_id.clone() });
+        })
+        .await;
+    node_chain.handle_received_messages().await;
+    let response = node_chain
+        .graphql_query(application_id, "query { pendingPurges { contentId nodeId } }")
+        .await
+        .response;
+    assert_eq!(response["pendingPurges"], json!([{ "contentId": content_id, "nodeId": "node-1" }]));
+
+    node_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::AcknowledgePurge { content_id: content_id.clone(), node_id: "node-1".to_string() },
+            );
+        })
+        .await;
+    owner_chain.handle_received_messages().await;
+
+    let query = format!("query {{ purge(contentId: \"{content_id}\") {{ targets {{ nodeId status }} }} }}");
+    let response = owner_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(
+        response["purge"]["targets"],
+        json!([{ "nodeId": "node-1", "status": "ACKNOWLEDGED" }])
+    );
+    assert_eq!(replicas(&owner_chain, application_id, &content_id).await, json!([]));
+}
+
+/// Content bytes are shipped to another chain and can be downloaded there
+#[tokio::test(flavor = "multi_thread")]
+async fn content_is_replicated_to_another_chain() {
+    let (_validator, owner_chain, node_chain, application_id) = setup().await;
+    let content_id = upload(&owner_chain, application_id, b"Bytes for everyone").await;
+
+    owner_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::Replicate { content_id: content_id.clone(), target_chain: node_chain.id() },
+            );
+        })
+        .await;
+    node_chain.handle_received_messages().await;
+    owner_chain.handle_received_messages().await;
+
+    let query = format!("query {{ replications(contentId: \"{content_id}\") {{ status }} }}");
+    let response = owner_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["replications"], json!([{ "status": "REPLICATED" }]));
+
+    let query = format!("query {{ getContentMetadata(contentId: \"{content_id}\") {{ size contentHash }} }}");
+    let response = node_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(
+        response["getContentMetadata"],
+        json!({ "size": 18, "contentHash": content_id })
+    );
+}
+
+/// Content is stored on its shard chain, and downloads elsewhere fetch a verified copy from it
+#[tokio::test(flavor = "multi_thread")]
+async fn content_is_routed_to_its_shard() {
+    let (validator, module_id) = TestValidator::with_current_module::<DCDNAbi, DCDNParameters, ()>().await;
+    let mut client_chain = validator.new_chain().await;
+    let shard_chain = validator.new_chain().await;
+    let parameters = DCDNParameters { shard_chains: vec![shard_chain.id()], ..DCDNParameters::default() };
+    let application_id = client_chain.create_application(module_id, parameters, (), vec![]).await;
+
+    let content_id = upload(&client_chain, application_id, b"Sharded bytes").await;
+    shard_chain.handle_received_messages().await;
+
+    let query = format!("query {{ shardChain(contentId: \"{content_id}\") }}");
+    let response = client_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["shardChain"], json!(shard_chain.id()));
+
+    let query = format!("query {{ getContentMetadata(contentId: \"{content_id}\") {{ size }} }}");
+    let response = client_chain.graphql_query(application_id, query.clone()).await.response;
+    assert_eq!(response["getContentMetadata"], json!(null));
+    let response = shard_chain.graphql_query(application_id, query.clone()).await.response;
+    assert_eq!(response["getContentMetadata"], json!({ "size": 13 }));
+
+    client_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::Download { content_id: content_id.clone() });
+        })
+        .await;
+    shard_chain.handle_received_messages().await;
+    client_chain.handle_received_messages().await;
+
+    let response = client_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["getContentMetadata"], json!({ "size": 13 }));
+    let stats_query = format!("query {{ contentStats(contentId: \"{content_id}\") {{ accessCount }} }}");
+    let response = shard_chain.graphql_query(application_id, stats_query).await.response;
+    assert_eq!(response["contentStats"], json!({ "accessCount": 1 }));
+}
+
+/// Nodes register on their own chains and the registry chain keeps the network totals
+#[tokio::test(flavor = "multi_thread")]
+async fn nodes_are_aggregated_on_registry_chain() {
+    let (validator, registry_chain, node_chain, application_id) = setup().await;
+    register_node(&validator, &node_chain, application_id, "node-1").await;
+    registry_chain.handle_received_messages().await;
+
+    let query = "query { getNodeCount getTotalCapacity getNode(nodeId: \"node-1\") { id } }";
+    let response = registry_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(
+        response,
+        json!({ "getNodeCount": 1, "getTotalCapacity": 1_000_000, "getNode": { "id": "node-1" } })
+    );
+    let response = node_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["getNodeCount"], json!(0));
+
+    node_chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::Heartbeat { node_id: "node-1".to_string() })
+                .with_operation(
+                    application_id,
+                    Operation::UpdateCapacity { node_id: "node-1".to_string(), capacity: 3_000_000 },
+                );
+        })
+        .await;
+    registry_chain.handle_received_messages().await;
+    let response = registry_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["getTotalCapacity"], json!(3_000_000));
+
+    node_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::DeregisterNode { node_id: "node-1".to_string() });
+        })
+        .await;
+    registry_chain.handle_received_messages().await;
+    let response = registry_chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["getNodeCount"], json!(0));
+    assert_eq!(response["getTotalCapacity"], json!(0));
+}
+
+async fn setup() -> (TestValidator, ActiveChain, ActiveChain, ApplicationId<DCDNAbi>) {
+    let (validator, module_id) = TestValidator::with_current_module::<DCDNAbi, DCDNParameters, ()>().await;
+    let mut owner_chain = validator.new_chain().await;
+    let node_chain = validator.new_chain().await;
+    let application_id = owner_chain.create_application(module_id, DCDNParameters::default(), (), vec![]).await;
+    (validator, owner_chain, node_chain, application_id)
+}
+
+async fn upload(chain: &ActiveChain, application_id: ApplicationId<DCDNAbi>, content: &[u8]) -> String {
+    let metadata = ContentMetadata {
+        name: "file.bin".to_string(),
+        size: content.len() as u64,
+        content_type: "application/octet-stream".to_string(),
+        owner: "owner".to_string(),
+        created_at: 0,
+        expires_at: None,
+        content_hash: None,
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::Upload { content: content.to_vec(), metadata });
+        })
+        .await;
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Funds the node chain's owner from the admin chain and registers a node with the minimum stake
+async fn register_node(
+    validator: &TestValidator,
+    chain: &ActiveChain,
+    application_id: ApplicationId<DCDNAbi>,
+    node_id: &str,
+) {
+    let operator = AccountOwner::from(chain.public_key());
+    let admin_chain = validator.get_chain(&validator.admin_chain_id());
+    admin_chain
+        .add_block(|block| {
+            block.with_native_token_transfer(
+                AccountOwner::CHAIN,
+                Account::new(chain.id(), operator),
+                MIN_NODE_STAKE.saturating_add(Amount::ONE),
+            );
+        })
+        .await;
+    chain.handle_received_messages().await;
+
+    let profile = NodeProfile {
+        endpoints: vec![NodeEndpoint {
+            scheme: "https".to_string(),
+            host: "edge.example.com".to_string(),
+            port: 443,
+        }],
+        protocols: vec!["h2".to_string()],
+        max_object_size: 1 << 30,
+        software_version: "1.0.0".to_string(),
+    };
+    let location = NodeLocation {
+        latitude_micros: 52_520_000,
+        longitude_micros: 13_405_000,
+        region: "eu-central".to_string(),
+        country_code: "DE".to_string(),
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterNode {
+                    node_id: node_id.to_string(),
+                    location,
+                    capacity: 1_000_000,
+                    bandwidth: 10_000,
+                    profile,
+                    stake: MIN_NODE_STAKE,
+                },
+            );
+        })
+        .await;
+}
+
+async fn request_remote_cache(
+    owner_chain: &ActiveChain,
+    node_chain: &ActiveChain,
+    application_id: ApplicationId<DCDNAbi>,
+    content_id: &str,
+    node_id: &str,
+) {
+    owner_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RequestRemoteCache {
+                    content_id: content_id.to_string(),
+                    node_id: node_id.to_string(),
+                    node_chain: node_chain.id(),
+                },
+            );
+        })
+        .await;
+}
+
+async fn remote_cache_status(
+    chain: &ActiveChain,
+    application_id: ApplicationId<DCDNAbi>,
+    content_id: &str,
+) -> serde_json::Value {
+    let query = format!("query {{ remoteCaches(contentId: \"{content_id}\") {{ status }} }}");
+    let response = chain.graphql_query(application_id, query).await.response;
+    response["remoteCaches"][0]["status"].clone()
+}
+
+async fn replicas(
+    chain: &ActiveChain,
+    application_id: ApplicationId<DCDNAbi>,
+    content_id: &str,
+) -> serde_json::Value {
+    let query = format!("query {{ contentReplicas(contentId: \"{content_id}\") {{ nodeId }} }}");
+    let response = chain.graphql_query(application_id, query).await.response;
+    response["contentReplicas"].clone()
+}
@@ -0,0 +1,125 @@
+// Integration testing for the queries clients use to pick a node.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use dcdn::{
+    ContentMetadata, DCDNAbi, DCDNParameters, NodeEndpoint, NodeLocation, NodeProfile, Operation, MIN_NODE_STAKE,
+};
+use linera_sdk::{
+    linera_base_types::{Account, AccountOwner, Amount},
+    test::TestValidator,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// Nodes closer to the client, or in its region, are ranked first
+#[tokio::test(flavor = "multi_thread")]
+async fn route_prefers_nearby_nodes() {
+    let (validator, module_id) = TestValidator::with_current_module::<DCDNAbi, DCDNParameters, ()>().await;
+    let mut chain = validator.new_chain().await;
+    let application_id = chain.create_application(module_id, DCDNParameters::default(), (), vec![]).await;
+
+    let operator = AccountOwner::from(chain.public_key());
+    let admin_chain = validator.get_chain(&validator.admin_chain_id());
+    admin_chain
+        .add_block(|block| {
+            block.with_native_token_transfer(
+                AccountOwner::CHAIN,
+                Account::new(chain.id(), operator),
+                MIN_NODE_STAKE.saturating_mul(2).saturating_add(Amount::ONE),
+            );
+        })
+        .await;
+    chain.handle_received_messages().await;
+
+    let content = b"Bytes served worldwide".to_vec();
+    let content_id = format!("{:x}", Sha256::digest(&content));
+    let metadata = ContentMetadata {
+        name: "file.bin".to_string(),
+        size: content.len() as u64,
+        content_type: "application/octet-stream".to_string(),
+        owner: "owner".to_string(),
+        created_at: 0,
+        expires_at: None,
+        content_hash: None,
+    };
+    let sydney = NodeLocation {
+        latitude_micros: -33_868_000,
+        longitude_micros: 151_209_000,
+        region: "ap-southeast".to_string(),
+        country_code: "AU".to_string(),
+    };
+    let berlin = NodeLocation {
+        latitude_micros: 52_520_000,
+        longitude_micros: 13_405_000,
+        region: "eu-central".to_string(),
+        country_code: "DE".to_string(),
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::Upload { content, metadata });
+            for (node_id, location) in [("sydney", sydney), ("berlin", berlin)] {
+                block
+                    .with_operation(
+                        application_id,
+                        Operation::RegisterNode {
+                            node_id: node_id.to_string(),
+                            location,
+                            capacity: 1_000_000,
+                            bandwidth: 10_000,
+                            profile: edge_profile(),
+                            stake: MIN_NODE_STAKE,
+                        },
+                    )
+                    .with_operation(
+                        application_id,
+                        Operation::UpdateAvailability {
+                            content_id: content_id.clone(),
+                            node_id: node_id.to_string(),
+                            available: true,
+                        },
+                    );
+            }
+        })
+        .await;
+
+    // A client in Paris
+    let query = format!(
+        "query {{ route(contentId: \"{content_id}\", clientRegion: \"eu-west\", latitude: 48.857, longitude: 2.352) {{ nodeId }} }}"
+    );
+    let response = chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["route"], json!([{ "nodeId": "berlin" }, { "nodeId": "sydney" }]));
+
+    // A client in Melbourne
+    let query = format!(
+        "query {{ route(contentId: \"{content_id}\", clientRegion: \"ap-southeast\", latitude: -37.814, longitude: 144.963, limit: 1) {{ nodeId }} }}"
+    );
+    let response = chain.graphql_query(application_id, query).await.response;
+    assert_eq!(response["route"], json!([{ "nodeId": "sydney" }]));
+
+    // A client that only knows its region
+    let query = format!(
+        "query {{ route(contentId: \"{content_id}\", clientRegion: \"eu-central\") {{ nodeId sameRegion distanceKm }} }}"
+    );
+    let response = chain.graphql_query(application_id, query).await.response;
+    assert_eq!(
+        response["route"],
+        json!([
+            { "nodeId": "berlin", "sameRegion": true, "distanceKm": null },
+            { "nodeId": "sydney", "sameRegion": false, "distanceKm": null },
+        ])
+    );
+}
+
+fn edge_profile() -> NodeProfile {
+    NodeProfile {
+        endpoints: vec![NodeEndpoint {
+            scheme: "https".to_string(),
+            host: "edge.example.com".to_string(),
+            port: 443,
+        }],
+        protocols: vec!["h2".to_string()],
+        max_object_size: 1 << 30,
+        software_version: "1.0.0".to_string(),
+    }
+}
@@ -2,18 +2,18 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use dcdn::{Operation, ContentMetadata};
+use dcdn::{Operation, ContentMetadata, DCDNParameters, DEFAULT_MAX_UPLOAD_SIZE};
 use linera_sdk::test::{TestValidator};
 
 /// Tests content upload and download functionality
 #[tokio::test(flavor = "multi_thread")]
 async fn test_content_flow() {
     let (validator, module_id) =
-        TestValidator::with_current_module::<dcdn::DCDNAbi, (), ()>().await;
+        TestValidator::with_current_module::<dcdn::DCDNAbi, DCDNParameters, ()>().await;
     let mut chain = validator.new_chain().await;
 
     let application_id = chain
-        .create_application(module_id, (), (), vec![])
+        .create_application(module_id, DCDNParameters::default(), (), vec![])
         .await;
 
     // Upload content (content ID will be generated from hash)
@@ -40,4 +40,13 @@ async fn test_content_flow() {
 
     // For now, just verify that the application was created successfully
     // The original test logic was too complex for this basic test setup
+
+    let response = chain
+        .graphql_query(application_id, "query { config { maxUploadSize nodeAdmission } }")
+        .await
+        .response;
+    assert_eq!(
+        response["config"],
+        serde_json::json!({ "maxUploadSize": DEFAULT_MAX_UPLOAD_SIZE, "nodeAdmission": "OPEN" })
+    );
 }
\ No newline at end of file